@@ -0,0 +1,51 @@
+//! Compares the dense and sparse storage of `block::Canvas`.
+//!
+//! Run with `cargo bench`.
+
+extern crate drawille;
+extern crate test;
+
+use test::Bencher;
+use drawille::block::{Canvas, Color, Indexed};
+
+static WIDTH: i32 = 200;
+static HEIGHT: i32 = 100;
+
+/// Fills every pixel of the canvas with a different colour, as drawing an image would.
+fn fill(canvas: &mut Canvas) {
+    for y in range(0, HEIGHT) {
+        for x in range(0, WIDTH) {
+            canvas.set(x, y, colour(x, y));
+        }
+    }
+}
+
+fn colour(x: i32, y: i32) -> Color {
+    Indexed(((x + y) % 256) as u8)
+}
+
+#[bench]
+fn set_dense(b: &mut Bencher) {
+    let mut canvas = Canvas::new(WIDTH as uint, HEIGHT as uint);
+    b.iter(|| fill(&mut canvas));
+}
+
+#[bench]
+fn set_sparse(b: &mut Bencher) {
+    let mut canvas = Canvas::new_sparse(WIDTH as uint, HEIGHT as uint);
+    b.iter(|| fill(&mut canvas));
+}
+
+#[bench]
+fn rows_dense(b: &mut Bencher) {
+    let mut canvas = Canvas::new(WIDTH as uint, HEIGHT as uint);
+    fill(&mut canvas);
+    b.iter(|| canvas.rows());
+}
+
+#[bench]
+fn rows_sparse(b: &mut Bencher) {
+    let mut canvas = Canvas::new_sparse(WIDTH as uint, HEIGHT as uint);
+    fill(&mut canvas);
+    b.iter(|| canvas.rows());
+}
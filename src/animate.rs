@@ -0,0 +1,146 @@
+//! Smooth animation of canvases in the terminal
+//!
+//! This replaces the `animate` function of the Python library
+//! [drawille](https://github.com/asciimoo/drawille). Rather than clearing the terminal before each
+//! frame, which flickers, each frame is drawn over the previous one, redrawing only the cells that
+//! have changed (see the `diff` module). The cursor is hidden while the animation runs, and left
+//! below the last frame once it ends.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::io;
+//! use drawille::animate::animate;
+//! use drawille::braille::Canvas;
+//!
+//! let mut canvas = Canvas::new(100, 40);
+//! let mut x = 0;
+//! animate(&mut io::stdout(), &mut canvas, 30, |canvas| {
+//!     canvas.clear();
+//!     canvas.line(x, 0, 100 - x, 39);
+//!     x += 1;
+//!     x <= 100
+//! }).unwrap();
+//! ```
+
+use std::io::{IoResult, Writer};
+use std::io::timer::Timer;
+use std::time::Duration;
+
+use diff;
+use surface::Render;
+
+static HIDE_CURSOR: &'static str = "\x1b[?25l";
+static SHOW_CURSOR: &'static str = "\x1b[?25h";
+static CLEAR_SCREEN: &'static str = "\x1b[2J";
+
+/// Animates `canvas` by calling `f` before every frame, stopping once `f` returns `false`.
+///
+/// Frames are drawn to `out` at up to `fps` frames per second; an `fps` of zero draws frames as
+/// quickly as possible. When the animation ends, the cursor is moved to the line below the last
+/// frame and shown again, even if writing to `out` fails part way through.
+pub fn animate<R: Render, W: Writer>(out: &mut W, canvas: &mut R, fps: uint,
+                                     f: |&mut R| -> bool) -> IoResult<()> {
+    try!(out.write_str(HIDE_CURSOR));
+    let mut rows = 0;
+    let result = run(out, canvas, fps, f, &mut rows);
+    try!(finish(out, rows));
+    result
+}
+
+/// Draws each frame produced by `frames` in turn, at up to `fps` frames per second.
+///
+/// This behaves like `animate`, but for animations whose frames are produced by an iterator.
+pub fn animate_frames<R: Render, W: Writer, I: Iterator<R>>(out: &mut W, frames: I, fps: uint)
+                                                           -> IoResult<()> {
+    try!(out.write_str(HIDE_CURSOR));
+    let mut rows = 0;
+    let result = run_frames(out, frames, fps, &mut rows);
+    try!(finish(out, rows));
+    result
+}
+
+/// Moves the cursor to the start of the line below a frame of `rows` rows, so that later output
+/// doesn't overwrite the frame, and shows the cursor again.
+fn finish<W: Writer>(out: &mut W, rows: uint) -> IoResult<()> {
+    try!(out.write_str(format!("\x1b[{};1H", rows + 1).as_slice()));
+    try!(out.write_str(SHOW_CURSOR));
+    out.flush()
+}
+
+/// Runs an animation as described for `animate`, keeping `rows` up to date with the number of
+/// rows of the last frame drawn.
+fn run<R: Render, W: Writer>(out: &mut W, canvas: &mut R, fps: uint,
+                             f: |&mut R| -> bool, rows: &mut uint) -> IoResult<()> {
+    try!(out.write_str(CLEAR_SCREEN));
+    let ticker = try!(ticker(fps));
+    let mut previous = vec![];
+    while f(canvas) {
+        let cells = try!(draw(out, previous.as_slice(), &*canvas));
+        *rows = cells.len();
+        previous = cells;
+        match ticker {
+            Some((_, ref ticks)) => ticks.recv(),
+            None => {},
+        }
+    }
+    Ok(())
+}
+
+/// Runs an animation as described for `animate_frames`, keeping `rows` up to date like `run`.
+fn run_frames<R: Render, W: Writer, I: Iterator<R>>(out: &mut W, mut frames: I, fps: uint,
+                                                   rows: &mut uint) -> IoResult<()> {
+    try!(out.write_str(CLEAR_SCREEN));
+    let ticker = try!(ticker(fps));
+    let mut previous = vec![];
+    for frame in frames {
+        let cells = try!(draw(out, previous.as_slice(), &frame));
+        *rows = cells.len();
+        previous = cells;
+        match ticker {
+            Some((_, ref ticks)) => ticks.recv(),
+            None => {},
+        }
+    }
+    Ok(())
+}
+
+/// Returns a channel that receives a message once per frame, along with the timer driving it
+/// (which must be kept alive for as long as the channel is used).
+fn ticker(fps: uint) -> IoResult<Option<(Timer, Receiver<()>)>> {
+    if fps == 0 {
+        return Ok(None);
+    }
+    let mut timer = try!(Timer::new());
+    let ticks = timer.periodic(Duration::milliseconds(1000 / fps as i64));
+    Ok(Some((timer, ticks)))
+}
+
+/// Draws a single frame over the previous one, returning its cells to compare the next frame
+/// against.
+fn draw<R: Render, W: Writer>(out: &mut W, previous: &[Vec<String>], frame: &R)
+                              -> IoResult<Vec<Vec<String>>> {
+    let cells = frame.cells();
+    try!(diff::write_diff(out, previous, cells.as_slice(), frame.rows().as_slice()));
+    try!(out.flush());
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::MemWriter;
+    use std::str;
+
+    use braille::Canvas;
+    use super::animate_frames;
+
+    #[test]
+    fn leaves_the_cursor_below_the_frame() {
+        let mut canvas = Canvas::new(4, 12);
+        canvas.set(1, 1);
+        let mut out = MemWriter::new();
+        animate_frames(&mut out, range(0u, 2).map(|_| canvas.clone()), 0).unwrap();
+        let output = str::from_utf8(out.get_ref()).unwrap();
+        assert!(output.ends_with("\x1b[4;1H\x1b[?25h"));
+    }
+}
@@ -1,7 +1,41 @@
+//! Terminal graphics using half blocks
+//!
+//! This module draws pictures using the half block characters (`▄` and `▀`), giving each
+//! character cell of the terminal two vertically stacked pixels that can each have their own
+//! colour.
+//!
+//! # Coordinates
+//!
+//! Pixels are addressed by pixel coordinates, where a cell at column `x` and row `y` holds the
+//! pixels `(x, 2 * y)` and `(x, 2 * y + 1)`. Text is addressed by cell coordinates, since each
+//! character fills a whole cell. `pixel_to_cell` and `cell_to_pixel` convert between the two.
+//!
+//! ```
+//! use drawille::block::{cell_to_pixel, pixel_to_cell};
+//!
+//! assert_eq!(pixel_to_cell(4, 7), (4, 3));
+//! assert_eq!(pixel_to_cell(4, -1), (4, -1));
+//! assert_eq!(cell_to_pixel(4, 3), (4, 6));
+//! ```
+
 use std::collections::HashMap;
 use std::cmp;
 use std::default::Default;
+use std::i32;
 use std::fmt::{Show, Formatter, FormatError};
+use std::io::{IoResult, Writer};
+
+use shapes;
+use surface::{Render, Surface};
+use term::{ColorDepth, Monochrome, Colors8, Colors16, Colors256, TrueColor};
+use super::{Error, OutOfBounds, TextCell, Overflow, Expand};
+use super::{check_dimensions, div_floor, mod_floor, round_point};
+
+/// The most cells a dense grid may hold. Larger canvases are created with sparse storage, and a
+/// canvas whose drawing would grow its grid beyond this switches to sparse storage instead.
+static MAX_DENSE_CELLS: uint = 1 << 22;
+/// The fewest extra columns or rows a dense grid grows by, beyond the pixel that made it grow.
+static MIN_SLACK: i64 = 16;
 
 #[deriving(Show, Clone, PartialEq, Eq)]
 pub enum Color {
@@ -13,6 +47,215 @@ pub enum Color {
     Magenta,
     Cyan,
     White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// The terminal's default colour, which lets a themed terminal background show through.
+    Transparent,
+    /// A colour from the terminal's 256-colour palette.
+    Indexed(u8),
+    /// A 24-bit colour, given as red, green and blue components.
+    Rgb(u8, u8, u8),
+}
+
+/// Returns the cell coordinates of the cell containing the pixel at the given pixel coordinates.
+pub fn pixel_to_cell(x: i32, y: i32) -> (i32, i32) {
+    (x, div_floor(y, 2))
+}
+
+/// Returns the pixel coordinates of the upper pixel in the cell at the given cell coordinates.
+pub fn cell_to_pixel(x: i32, y: i32) -> (i32, i32) {
+    (x, y * 2)
+}
+
+/// The colours of the first sixteen entries of the xterm palette.
+static PALETTE: [(u8, u8, u8), ..16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+/// The intensities of each component used by the 6x6x6 colour cube in the 256-colour palette.
+static CUBE_LEVELS: [u8, ..6] = [0, 95, 135, 175, 215, 255];
+
+impl Color {
+    /// Returns the closest colour to this one that can be displayed with the given colour depth.
+    ///
+    /// Colours that can already be displayed are returned unchanged; other colours are replaced by
+    /// the perceptually nearest entry of the available palette, measured in the CIELAB colour
+    /// space. Under `Monochrome`, where no colour can be displayed, colours are also returned
+    /// unchanged. `Transparent` is available at every depth.
+    pub fn quantise(&self, depth: ColorDepth) -> Color {
+        match (depth, *self) {
+            (_, Transparent) => Transparent,
+            (Monochrome, c) | (TrueColor, c) => c,
+            (Colors256, Rgb(r, g, b)) => Indexed(nearest_256(r, g, b)),
+            (Colors256, c) => c,
+            (Colors16, Indexed(n)) if n < 16 => Color::from_index(n),
+            (Colors16, Indexed(_)) | (Colors16, Rgb(..)) => Color::from_index(nearest(self, 16)),
+            (Colors16, c) => c,
+            (Colors8, Indexed(n)) if n < 8 => Color::from_index(n),
+            (Colors8, Indexed(_)) | (Colors8, Rgb(..)) => Color::from_index(nearest(self, 8)),
+            (Colors8, c) if c.ansi_index() < 8 => c,
+            (Colors8, _) => Color::from_index(nearest(self, 8)),
+        }
+    }
+
+    /// Returns the red, green and blue components of this colour, assuming the terminal uses the
+    /// default xterm palette. `Transparent` is assumed to be black.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            Transparent => (0, 0, 0),
+            Indexed(n) if n < 16 => PALETTE[n as uint],
+            Indexed(n) if n < 232 => {
+                let i = (n - 16) as uint;
+                (CUBE_LEVELS[i / 36], CUBE_LEVELS[(i / 6) % 6], CUBE_LEVELS[i % 6])
+            }
+            Indexed(n) => {
+                let v = 8 + 10 * (n - 232);
+                (v, v, v)
+            }
+            Rgb(r, g, b) => (r, g, b),
+            basic => PALETTE[basic.ansi_index()],
+        }
+    }
+
+    /// Returns the colour for an entry in the first sixteen colours of the palette, preferring the
+    /// named base colours where possible.
+    fn from_index(n: u8) -> Color {
+        match n {
+            0 => Black,
+            1 => Red,
+            2 => Green,
+            3 => Yellow,
+            4 => Blue,
+            5 => Magenta,
+            6 => Cyan,
+            7 => White,
+            8 => BrightBlack,
+            9 => BrightRed,
+            10 => BrightGreen,
+            11 => BrightYellow,
+            12 => BrightBlue,
+            13 => BrightMagenta,
+            14 => BrightCyan,
+            15 => BrightWhite,
+            n => Indexed(n),
+        }
+    }
+
+    /// Detects whether the colour is closer to white than to black, for deciding which pixels to
+    /// show when colour is not available.
+    fn is_light(&self) -> bool {
+        let (r, g, b) = self.rgb();
+        let (l, _, _) = lab(r, g, b);
+        l > 50.0
+    }
+
+    /// Returns the parameters of the SGR escape sequence selecting this colour, as the background
+    /// colour if `background` is true and as the foreground colour otherwise.
+    ///
+    /// For example, `Red.sgr(false)` is `"31"`, to be used as `"\x1b[31m"`.
+    pub fn sgr(&self, background: bool) -> String {
+        let kind = if background { 4u } else { 3 };
+        match *self {
+            Transparent => format!("{}9", kind),
+            Indexed(n) if n < 16 => Color::from_index(n).sgr(background),
+            Indexed(n) => format!("{}8;5;{}", kind, n),
+            Rgb(r, g, b) => format!("{}8;2;{};{};{}", kind, r, g, b),
+            named => match named.ansi_index() {
+                i if i < 8 => format!("{}{}", kind, i),
+                i => format!("{}{}", if background { 10u } else { 9 }, i - 8),
+            },
+        }
+    }
+
+    /// Returns the index of one of the sixteen named colours in the ANSI palette.
+    fn ansi_index(&self) -> uint {
+        match *self {
+            Black => 0,
+            Red => 1,
+            Green => 2,
+            Yellow => 3,
+            Blue => 4,
+            Magenta => 5,
+            Cyan => 6,
+            White => 7,
+            BrightBlack => 8,
+            BrightRed => 9,
+            BrightGreen => 10,
+            BrightYellow => 11,
+            BrightBlue => 12,
+            BrightMagenta => 13,
+            BrightCyan => 14,
+            BrightWhite => 15,
+            Transparent | Indexed(_) | Rgb(..) => fail!("not a named ANSI colour"),
+        }
+    }
+}
+
+/// Converts an sRGB colour to the CIELAB colour space, using the D65 white point.
+fn lab(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    fn linear(c: u8) -> f64 {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    }
+    fn f(t: f64) -> f64 {
+        if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 }
+    }
+
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = f((0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047);
+    let y = f(0.2126 * r + 0.7152 * g + 0.0722 * b);
+    let z = f((0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883);
+    (116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+}
+
+/// Returns the squared distance between two colours in the CIELAB colour space.
+fn distance(first: (u8, u8, u8), second: (u8, u8, u8)) -> f64 {
+    let ((r1, g1, b1), (r2, g2, b2)) = (first, second);
+    let (l1, a1, bb1) = lab(r1, g1, b1);
+    let (l2, a2, bb2) = lab(r2, g2, b2);
+    (l1 - l2) * (l1 - l2) + (a1 - a2) * (a1 - a2) + (bb1 - bb2) * (bb1 - bb2)
+}
+
+/// Returns the index of the entry among the first `n` palette entries closest to `c`.
+fn nearest(c: &Color, n: uint) -> u8 {
+    let rgb = c.rgb();
+    let mut best = 0;
+    for i in range(1, n) {
+        if distance(rgb, PALETTE[i]) < distance(rgb, PALETTE[best]) {
+            best = i;
+        }
+    }
+    best as u8
+}
+
+/// Returns the index of the entry in the 256-colour palette closest to the given colour.
+///
+/// Only the colour cube and the greyscale ramp are considered, since the first sixteen entries are
+/// often changed by terminal themes.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    fn level(v: u8) -> uint {
+        range(0u, 6).min_by(|&i| (CUBE_LEVELS[i] as int - v as int).abs()).unwrap()
+    }
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (16 + 36 * ri + 6 * gi + bi) as u8;
+
+    let average = (r as int + g as int + b as int) / 3;
+    let grey = (232 + cmp::min(cmp::max((average - 3) / 10, 0), 23)) as u8;
+
+    if distance((r, g, b), Indexed(cube).rgb()) <= distance((r, g, b), Indexed(grey).rgb()) {
+        cube
+    } else {
+        grey
+    }
 }
 
 #[deriving(Clone, PartialEq, Eq)]
@@ -22,135 +265,588 @@ impl Show for ColorPair {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
         // TODO: add Windows support if needed
         let ColorPair(first, second) = *self;
-        let finit = "\x1b[0;";
-        let fend = first as u32;
-        let f = format!("{}4{}m", finit, fend);
-        let sinit = "\x1b[";
-        let send = second as u32;
-        let s = format!("{}3{}m", sinit, send);
+        let f = format!("\x1b[0;{}m", first.sgr(true));
+        let s = format!("\x1b[{}m", second.sgr(false));
         try!(write!(fmt, "{}{}", f, s));
         Ok(())
     }
 }
 
+impl ColorPair {
+    fn quantise(&self, depth: ColorDepth) -> ColorPair {
+        let ColorPair(first, second) = *self;
+        ColorPair(first.quantise(depth), second.quantise(depth))
+    }
+}
+
+/// The colours and attributes used to draw text onto a `Canvas`.
+///
+/// # Example
+///
+/// ```
+/// use drawille::block::{Canvas, Style, Red, Transparent};
+///
+/// let mut canvas = Canvas::new(20, 4);
+/// let mut style = Style::new(Red, Transparent);
+/// style.bold = true;
+/// canvas.text_styled(0, 0, style, "Warning");
+/// ```
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub strikethrough: bool,
+}
+
+impl Style {
+    /// Creates a `Style` with the given colours and no attributes.
+    pub fn new(fg: Color, bg: Color) -> Style {
+        Style {
+            fg: fg,
+            bg: bg,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+            blink: false,
+            reverse: false,
+            strikethrough: false,
+        }
+    }
+
+    fn quantise(&self, depth: ColorDepth) -> Style {
+        Style { fg: self.fg.quantise(depth), bg: self.bg.quantise(depth), ..*self }
+    }
+
+    /// Returns the SGR parameters selecting the style's attributes, without its colours.
+    fn attributes(&self) -> Vec<&'static str> {
+        let attributes = [(self.bold, "1"), (self.dim, "2"), (self.italic, "3"),
+                          (self.underline, "4"), (self.blink, "5"), (self.reverse, "7"),
+                          (self.strikethrough, "9")];
+        attributes.iter().filter(|&&(on, _)| on).map(|&(_, code)| code).collect()
+    }
+}
+
+impl Show for Style {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
+        try!(write!(fmt, "\x1b[0;{};{}", self.bg.sgr(true), self.fg.sgr(false)));
+        for code in self.attributes().iter() {
+            try!(write!(fmt, ";{}", code));
+        }
+        write!(fmt, "m")
+    }
+}
+
 #[deriving(Clone, PartialEq, Eq)]
 enum Pixel {
-    Char(ColorPair, char),
+    Char(Style, char),
     Pair(ColorPair),
 }
 
 impl Default for Pixel {
     fn default() -> Pixel {
-        Char(ColorPair(Black, Black), ' ')
+        Char(Style::new(Transparent, Transparent), ' ')
     }
 }
 
 impl Show for Pixel {
     fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
-        match *self {
-            Char(cp, a) => try!(write!(f, "{}{}", cp, a)),
-            Pair(a) => try!(write!(f, "{}▄", a)),
+        let (style, c) = self.resolve(TrueColor);
+        write!(f, "{}{}", style, c)
+    }
+}
+
+impl Pixel {
+    /// Returns the style and character to draw for the pixel, using only the colours available
+    /// with the given colour depth.
+    ///
+    /// Without any colour, each half of a pixel pair is either drawn in the terminal's foreground
+    /// colour or left blank, depending on whether it is light or dark. Text keeps its attributes.
+    fn resolve(&self, depth: ColorDepth) -> (Style, char) {
+        match (*self, depth) {
+            (Char(style, c), Monochrome) => {
+                (Style { fg: Transparent, bg: Transparent, ..style }, c)
+            }
+            (Pair(ColorPair(top, bottom)), Monochrome) => {
+                let c = match (top.is_light(), bottom.is_light()) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                };
+                (Style::new(Transparent, Transparent), c)
+            }
+            (Char(style, c), _) => (style.quantise(depth), c),
+            // A `Transparent` half is left to the terminal's default background, which only a
+            // blank or the unused half of a block character can show.
+            (Pair(pair), _) => match pair.quantise(depth) {
+                ColorPair(Transparent, Transparent) => (Style::new(Transparent, Transparent), ' '),
+                ColorPair(top, Transparent) => (Style::new(top, Transparent), '▀'),
+                ColorPair(top, bottom) => (Style::new(bottom, top), '▄'),
+            },
+        }
+    }
+
+    /// Renders the pixel so that it displays correctly whatever state the terminal was left in.
+    fn render(&self, depth: ColorDepth) -> String {
+        let (style, c) = self.resolve(depth);
+        let default = Style::new(Transparent, Transparent);
+        if depth != Monochrome {
+            format!("{}{}", style, c)
+        } else if style == default {
+            String::from_char(1, c)
+        } else {
+            format!("{}{}\x1b[0m", transition(&default, &style, depth), c)
         }
-        Ok(())
     }
 }
 
-impl IndexMut<uint, Color> for Pixel {
-    fn index_mut<'a>(&'a mut self, index: &uint) -> &'a mut Color {
-        let cp = match *self {
-            Pair(ref mut cp) => cp,
-            _ => fail!("indexing a text pixel"),
-        };
-        let ColorPair(ref mut c1, ref mut c2) = *cp;
-        match *index {
-            0 => c1,
-            1 => c2,
-            _ => fail!("ColorPair index out of bounds"),
+/// Returns the shortest escape sequence that changes the terminal from drawing with `from` to
+/// drawing with `to`, which is empty if the two styles are the same.
+///
+/// Attributes can't be turned off individually on every terminal, so if any attribute has to be
+/// turned off everything is reset first.
+fn transition(from: &Style, to: &Style, depth: ColorDepth) -> String {
+    if from == to {
+        return String::new();
+    }
+
+    let mut params = vec![];
+    let (old, new) = (from.attributes(), to.attributes());
+    let from = if old.iter().any(|a| !new.contains(a)) {
+        params.push("0".to_string());
+        Style::new(Transparent, Transparent)
+    } else {
+        *from
+    };
+    if depth != Monochrome {
+        if to.bg != from.bg {
+            params.push(to.bg.sgr(true));
+        }
+        if to.fg != from.fg {
+            params.push(to.fg.sgr(false));
         }
     }
+    let old = from.attributes();
+    for a in new.iter().filter(|a| !old.contains(*a)) {
+        params.push(a.to_string());
+    }
+
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("\x1b[{}m", params.connect(";"))
+    }
 }
 
 impl Pixel {
-    fn index(&self, index: uint) -> Color {
-        let cp = match *self {
+    /// Returns the colour of the upper (`half == 0`) or lower (`half == 1`) pixel, or `None` if
+    /// this is text.
+    fn color(&self, half: uint) -> Option<Color> {
+        match *self {
+            Pair(ColorPair(top, _)) if half == 0 => Some(top),
+            Pair(ColorPair(_, bottom)) => Some(bottom),
+            Char(_, _) => None,
+        }
+    }
+
+    /// Sets the colour of the upper (`half == 0`) or lower (`half == 1`) pixel, replacing any
+    /// text.
+    fn set_color(&mut self, half: uint, c: Color) {
+        let ColorPair(top, bottom) = match *self {
             Pair(cp) => cp,
-            _ => fail!("indexing a text pixel"),
+            Char(_, _) => ColorPair(Transparent, Transparent),
         };
-        let ColorPair(c1, c2) = cp;
-        match index {
-            0 => c1,
-            1 => c2,
-            _ => fail!("ColorPair index out of bounds"),
+        *self = Pair(if half == 0 { ColorPair(c, bottom) } else { ColorPair(top, c) });
+    }
+}
+
+/// A dense, row-major grid of pixels that grows as pixels are drawn outside of it.
+#[deriving(Clone, Show, PartialEq, Eq)]
+struct Grid {
+    origin: (i32, i32),
+    width: uint,
+    height: uint,
+    pixels: Vec<Pixel>,
+    /// The smallest area containing every pixel that has been drawn to, as
+    /// `(minx, miny, maxx, maxy)` where the maxima are exclusive.
+    extent: Option<(i32, i32, i32, i32)>,
+}
+
+impl Grid {
+    fn new(width: uint, height: uint) -> Grid {
+        Grid {
+            origin: (0, 0),
+            width: width,
+            height: height,
+            pixels: Vec::from_elem(width * height, Default::default()),
+            extent: None,
         }
     }
+
+    fn index(&self, x: i32, y: i32) -> Option<uint> {
+        let (ox, oy) = self.origin;
+        let (dx, dy) = (x as i64 - ox as i64, y as i64 - oy as i64);
+        if dx < 0 || dy < 0 || dx >= self.width as i64 || dy >= self.height as i64 {
+            None
+        } else {
+            Some(dy as uint * self.width + dx as uint)
+        }
+    }
+
+    fn find<'a>(&'a self, x: i32, y: i32) -> Option<&'a Pixel> {
+        self.index(x, y).map(|i| &self.pixels[i])
+    }
+
+    /// Returns the pixel at `(x, y)`, which must already be in the grid; see `Grid::reserve`.
+    ///
+    /// `x` must be less than `i32::MAX`, so that the extent's exclusive maximum fits.
+    fn find_or_insert<'a>(&'a mut self, x: i32, y: i32) -> &'a mut Pixel {
+        self.extent = Some(match self.extent {
+            None => (x, y, x + 1, y + 1),
+            Some((minx, miny, maxx, maxy)) => {
+                (cmp::min(minx, x), cmp::min(miny, y), cmp::max(maxx, x + 1), cmp::max(maxy, y + 1))
+            }
+        });
+        let i = self.index(x, y).unwrap();
+        self.pixels.get_mut(i)
+    }
+
+    /// Grows the grid if necessary so that it includes `(x, y)`, returning false if it would have
+    /// to hold more than `MAX_DENSE_CELLS` cells.
+    fn reserve(&mut self, x: i32, y: i32) -> bool {
+        if self.index(x, y).is_some() {
+            return true;
+        }
+        let (ox, oy) = self.origin;
+        let (ox, oy, x, y) = (ox as i64, oy as i64, x as i64, y as i64);
+        let (w, h) = (self.width as i64, self.height as i64);
+        // Leave room for further growth in the same direction, so that drawing steadily outwards
+        // doesn't reallocate the grid for every pixel, even when it starts out empty, while
+        // keeping every cell addressable.
+        let (sx, sy) = (cmp::max(w / 2, MIN_SLACK), cmp::max(h / 2, MIN_SLACK));
+        let minx = cmp::max(i32::MIN as i64, if x < ox { x - sx } else { ox });
+        let miny = cmp::max(i32::MIN as i64, if y < oy { y - sy } else { oy });
+        let maxx = cmp::min(i32::MAX as i64, if x >= ox + w { x + 1 + sx } else { ox + w });
+        let maxy = cmp::min(i32::MAX as i64, if y >= oy + h { y + 1 + sy } else { oy + h });
+
+        let (width, height) = ((maxx - minx) as uint, (maxy - miny) as uint);
+        match width.checked_mul(&height) {
+            Some(cells) if cells <= MAX_DENSE_CELLS => {},
+            _ => return false,
+        }
+        let mut grid = Grid {
+            origin: (minx as i32, miny as i32),
+            width: width,
+            height: height,
+            pixels: Vec::from_elem(width * height, Default::default()),
+            extent: self.extent,
+        };
+        for row in range(0, self.height) {
+            for col in range(0, self.width) {
+                let i = grid.index((ox + col as i64) as i32, (oy + row as i64) as i32).unwrap();
+                *grid.pixels.get_mut(i) = self.pixels[row * self.width + col];
+            }
+        }
+        *self = grid;
+        true
+    }
+
+    /// Copies every pixel within the extent of the grid into a map, as used by sparse storage.
+    fn to_map(&self) -> HashMap<(i32, i32), Pixel> {
+        let mut map = HashMap::new();
+        match self.extent {
+            Some((minx, miny, maxx, maxy)) => {
+                for y in range(miny, maxy) {
+                    for x in range(minx, maxx) {
+                        map.insert((x, y), *self.find(x, y).unwrap());
+                    }
+                }
+            }
+            None => {},
+        }
+        map
+    }
+
+    fn clear(&mut self) {
+        for i in range(0, self.pixels.len()) {
+            *self.pixels.get_mut(i) = Default::default();
+        }
+        self.extent = None;
+    }
 }
 
+/// The ways a `Canvas` can store its pixels.
 #[deriving(Clone, Show, PartialEq, Eq)]
+enum Storage {
+    Sparse(HashMap<(i32, i32), Pixel>),
+    Dense(Grid),
+}
+
+impl Storage {
+    fn find<'a>(&'a self, x: i32, y: i32) -> Option<&'a Pixel> {
+        match *self {
+            Sparse(ref map) => map.find(&(x, y)),
+            Dense(ref grid) => grid.find(x, y),
+        }
+    }
+
+    /// Returns the pixel at `(x, y)`, inserting it if necessary.
+    ///
+    /// Dense storage that would have to grow too large to hold the pixel is switched to sparse
+    /// storage first.
+    fn find_or_insert<'a>(&'a mut self, x: i32, y: i32) -> &'a mut Pixel {
+        let overflowed = match *self {
+            Dense(ref mut grid) => if grid.reserve(x, y) { None } else { Some(grid.to_map()) },
+            Sparse(_) => None,
+        };
+        match overflowed {
+            Some(map) => *self = Sparse(map),
+            None => {},
+        }
+        match *self {
+            Sparse(ref mut map) => map.find_or_insert((x, y), Default::default()),
+            Dense(ref mut grid) => grid.find_or_insert(x, y),
+        }
+    }
+
+    /// Returns the smallest area containing every pixel that has been drawn to, as
+    /// `(minx, miny, maxx, maxy)` where the maxima are exclusive.
+    fn extent(&self) -> Option<(i32, i32, i32, i32)> {
+        match *self {
+            Sparse(ref map) if map.is_empty() => None,
+            Sparse(ref map) => Some((map.keys().map(|&(x, _)| x).min().unwrap(),
+                                     map.keys().map(|&(_, y)| y).min().unwrap(),
+                                     map.keys().map(|&(x, _)| x + 1).max().unwrap(),
+                                     map.keys().map(|&(_, y)| y + 1).max().unwrap())),
+            Dense(ref grid) => grid.extent,
+        }
+    }
+
+    fn clear(&mut self) {
+        match *self {
+            Sparse(ref mut map) => map.clear(),
+            Dense(ref mut grid) => grid.clear(),
+        }
+    }
+}
+
+#[deriving(Clone)]
 pub struct Canvas {
-    blocks: HashMap<(uint, uint), Pixel>,
+    blocks: Storage,
     width:  uint,
     height: uint,
+    overflow: Overflow,
+    depth: ColorDepth,
 }
 
 impl Canvas {
+    /// Creates a new `Canvas` with the given width and height in pixels.
+    ///
+    /// Pixels are stored in a dense grid covering the canvas, which is the fastest choice for
+    /// canvases that are mostly drawn on, such as images; see `Canvas::new_sparse`. Canvases of
+    /// more than about four million (2^22) cells, or whose drawing far outside the canvas would
+    /// make the grid that large, use sparse storage instead.
+    ///
+    /// Fails if the dimensions are too large to be addressed; see `Canvas::try_new`.
     pub fn new(width: uint, height: uint) -> Canvas {
+        Canvas::try_new(width, height).unwrap()
+    }
+
+    /// Creates a new `Canvas` like `Canvas::new`, returning an error if the dimensions are too
+    /// large to be addressed with `i32` coordinates.
+    pub fn try_new(width: uint, height: uint) -> Result<Canvas, Error> {
+        try!(check_dimensions(width, height));
+        let rows = (height + 1) / 2;
+        let blocks = match width.checked_mul(&rows) {
+            Some(cells) if cells <= MAX_DENSE_CELLS => Dense(Grid::new(width, rows)),
+            _ => Sparse(HashMap::new()),
+        };
+        Ok(Canvas::with_storage(blocks, width, height))
+    }
+
+    /// Creates a new `Canvas` with the given width and height in pixels, storing only the blocks
+    /// that have been drawn to.
+    ///
+    /// This uses much less memory than `Canvas::new` for huge canvases that are mostly empty, at
+    /// the cost of slower drawing and rendering.
+    ///
+    /// Fails if the dimensions are too large to be addressed; see `Canvas::try_new`.
+    pub fn new_sparse(width: uint, height: uint) -> Canvas {
+        Canvas::try_new_sparse(width, height).unwrap()
+    }
+
+    /// Creates a new sparse `Canvas` like `Canvas::new_sparse`, returning an error if the
+    /// dimensions are too large to be addressed with `i32` coordinates.
+    pub fn try_new_sparse(width: uint, height: uint) -> Result<Canvas, Error> {
+        try!(check_dimensions(width, height));
+        Ok(Canvas::with_storage(Sparse(HashMap::new()), width, height))
+    }
+
+    fn with_storage(blocks: Storage, width: uint, height: uint) -> Canvas {
         Canvas {
-            blocks: HashMap::new(),
-            width: width / 2,
-            height: height / 4,
+            blocks: blocks,
+            width: width,
+            height: (height + 1) / 2,
+            overflow: Expand,
+            depth: ColorDepth::detect(),
         }
     }
 
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    /// Sets the colour depth that output is restricted to.
+    ///
+    /// This defaults to the depth detected from the environment by `ColorDepth::detect`. Colours
+    /// that can't be displayed at the given depth are replaced by the nearest available colour
+    /// when the canvas is drawn; the canvas itself still stores the original colours.
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.depth = depth;
+    }
+
     pub fn clear(&mut self) {
         self.blocks.clear();
     }
 
-    pub fn text<S: Str>(&mut self, x: uint, y: uint, fg: Color, bg: Color, s: S) {
-        let (row, col) = (x, y / 2);
+    /// Writes text onto the canvas, starting at the given cell coordinates.
+    ///
+    /// Each character occupies a whole cell, so unlike `Canvas::set`, `y` is a row of cells rather
+    /// than of pixels; use `pixel_to_cell` to place text next to a pixel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drawille::block::{Canvas, White, Transparent};
+    /// use drawille::term::Monochrome;
+    ///
+    /// let mut canvas = Canvas::new(6, 4);
+    /// canvas.set_color_depth(Monochrome);
+    /// canvas.text(1, 1, White, Transparent, "hi");
+    /// assert_eq!(canvas.rows(), vec!["      ".to_string(), " hi   ".to_string()]);
+    /// ```
+    pub fn text<S: Str>(&mut self, x: i32, y: i32, fg: Color, bg: Color, s: S) {
+        self.text_styled(x, y, Style::new(fg, bg), s);
+    }
+
+    /// Writes text in the given style onto the canvas, starting at the given cell coordinates.
+    pub fn text_styled<S: Str>(&mut self, x: i32, y: i32, style: Style, s: S) {
         for (i, c) in s.as_slice().chars().enumerate() {
-            let block = self.blocks.find_or_insert((row + i, col), Default::default());
-            *block = Char(ColorPair(bg, fg), c);
+            let col = x + i as i32;
+            if !self.writable(col, y) {
+                continue;
+            }
+            *self.blocks.find_or_insert(col, y) = Char(style, c);
+        }
+    }
+
+    /// Sets the pixel at the given pixel coordinates to the colour `c`.
+    ///
+    /// If the pixel's cell held text, the text is removed.
+    pub fn set(&mut self, x: i32, y: i32, c: Color) {
+        let (col, row) = pixel_to_cell(x, y);
+        if self.writable(col, row) {
+            self.blocks.find_or_insert(col, row).set_color(mod_floor(y, 2) as uint, c);
         }
     }
 
-    pub fn set(&mut self, x: uint, y: uint, c: Color) {
-        let (row, col) = (x, y / 2);
-        let mut block = self.blocks.find_or_insert((row, col), Default::default());
-        match block {
-            ref mut a @ &Char(_, _) => **a = Pair(ColorPair(Black, Black)),
+    /// Sets the pixel at the given pixel coordinates to the colour `c`.
+    ///
+    /// Unlike `Canvas::set`, this fails rather than removing text from the pixel's cell, and fails
+    /// if the pixel is outside of a canvas that doesn't expand.
+    pub fn try_set(&mut self, x: i32, y: i32, c: Color) -> Result<(), Error> {
+        let (col, row) = pixel_to_cell(x, y);
+        if !self.writable(col, row) {
+            return Err(OutOfBounds(x, y));
+        }
+        match self.blocks.find(col, row) {
+            Some(&Char(_, _)) => return Err(TextCell(x, y)),
             _ => {},
         }
+        self.blocks.find_or_insert(col, row).set_color(mod_floor(y, 2) as uint, c);
+        Ok(())
+    }
+
+    /// Resets the pixel at the given pixel coordinates to `Transparent`.
+    pub fn unset(&mut self, x: i32, y: i32) {
+        self.set(x, y, Transparent);
+    }
 
-        block[y % 2] = c;
+    /// Resets the pixel at the given pixel coordinates to `Transparent`, failing in the same cases
+    /// as `Canvas::try_set`.
+    pub fn try_unset(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        self.try_set(x, y, Transparent)
     }
 
-    pub fn unset(&mut self, x: uint, y: uint) {
-        let (row, col) = (x, y / 2);
-        self.blocks.find_or_insert((row, col), Default::default())[y % 2] = Black;
+    /// Returns the colour of the pixel at the given pixel coordinates.
+    ///
+    /// Pixels that have never been drawn to, or whose cell holds text, are `Transparent`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drawille::block::{Canvas, Red, Transparent};
+    ///
+    /// let mut canvas = Canvas::new(10, 10);
+    /// canvas.set(3, 7, Red);
+    /// assert_eq!(canvas.get(3, 7), Red);
+    /// assert_eq!(canvas.get(3, 6), Transparent);
+    /// assert_eq!(canvas.get(7, 3), Transparent);
+    /// ```
+    pub fn get(&self, x: i32, y: i32) -> Color {
+        self.try_get(x, y).unwrap_or(Transparent)
     }
 
-    pub fn get(&self, x: uint, y: uint) -> Color {
-        let (col, row) = (x, y / 2);
-        let col = self.blocks.find(&(row, col));
-        
-        match col {
-            None => Black,
-            Some(c) => c.index(y % 2),
+    /// Returns the colour of the pixel at the given pixel coordinates, failing if its cell holds
+    /// text or if it is outside of a canvas that doesn't expand.
+    pub fn try_get(&self, x: i32, y: i32) -> Result<Color, Error> {
+        let (col, row) = pixel_to_cell(x, y);
+        if !self.writable(col, row) {
+            return Err(OutOfBounds(x, y));
+        }
+        match self.blocks.find(col, row) {
+            None => Ok(Transparent),
+            Some(pixel) => match pixel.color(mod_floor(y, 2) as uint) {
+                Some(c) => Ok(c),
+                None => Err(TextCell(x, y)),
+            },
         }
     }
 
+    /// Returns a `Vec` of each row of the `Canvas`.
+    ///
+    /// Escape sequences are only emitted where the colours or attributes change from one cell to
+    /// the next. Each row assumes the terminal starts with its default colours and attributes, and
+    /// restores them at its end.
     pub fn rows(&self) -> Vec<String> {
-        let maxrow = cmp::max(self.width, self.blocks.keys().map(|&(x, _)| x).max().unwrap_or(0));
-        let maxcol = cmp::max(self.height, self.blocks.keys().map(|&(_, y)| y).max().unwrap_or(0));
+        self.iter_rows().collect()
+    }
+
+    /// Returns an iterator over the rows of the `Canvas`, which renders each row only as it is
+    /// reached.
+    pub fn iter_rows<'a>(&'a self) -> Rows<'a> {
+        let (minx, miny, maxx, maxy) = self.bounds();
+        Rows { canvas: self, y: miny, minx: minx, maxx: maxx, maxy: maxy }
+    }
+
+    pub fn cells(&self) -> Vec<Vec<String>> {
+        let (minx, miny, maxx, maxy) = self.bounds();
 
         let mut result = vec![];
-        for y in range(0, maxcol + 1) {
-            let mut row = String::new();
-            for x in range(0, maxrow + 1) {
-                let col = *self.blocks.find(&(x, y)).unwrap_or(&Default::default());
-                row.push_str((format!("{}", col)).as_slice());
+        for y in range(miny, maxy) {
+            let mut row = vec![];
+            for x in range(minx, maxx) {
+                let col = *self.blocks.find(x, y).unwrap_or(&Default::default());
+                row.push(col.render(self.depth));
             }
-            result.push(format!("{}\x1b[0m", row));
+            result.push(row);
         }
         result
     }
@@ -159,34 +855,309 @@ impl Canvas {
         self.rows().connect("\n")
     }
 
-    pub fn line_vec(&self, x1: uint, y1: uint, x2: uint, y2: uint) -> Vec<(uint, uint)> {
-        let xdiff = cmp::max(x1, x2) - cmp::min(x1, x2);
-        let ydiff = cmp::max(y1, y2) - cmp::min(y1, y2);
-        let xdir = if x1 <= x2 { 1 } else { -1 };
-        let ydir = if y1 <= y2 { 1 } else { -1 };
+    /// Draws the canvas to `out` in the same form as `Canvas::frame`.
+    ///
+    /// Only a single row is held in memory at a time; to also avoid allocating that row for every
+    /// frame, use a `Renderer`.
+    pub fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        let (minx, miny, maxx, maxy) = self.bounds();
+        let mut row = String::new();
+        for y in range(miny, maxy) {
+            if y != miny {
+                try!(out.write_str("\n"));
+            }
+            row.truncate(0);
+            self.render_row(y, minx, maxx, &mut row);
+            try!(out.write_str(row.as_slice()));
+        }
+        Ok(())
+    }
 
-        let r = cmp::max(xdiff, ydiff);
+    pub fn line_vec(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> Vec<(i32, i32)> {
+        shapes::line_vec(x1, y1, x2, y2)
+    }
 
-        let mut result = vec![];
-        for i in range(0, r + 1) {
-            let mut x = x1;
-            let mut y = y1;
+    pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, c: Color) {
+        for (x, y) in shapes::line_points(x1, y1, x2, y2) {
+            self.set(x, y, c);
+        }
+    }
+
+    pub fn set_f64(&mut self, x: f64, y: f64, c: Color) {
+        match round_point(x, y) {
+            Some((x, y)) => self.set(x, y, c),
+            None => {},
+        }
+    }
+
+    pub fn unset_f64(&mut self, x: f64, y: f64) {
+        match round_point(x, y) {
+            Some((x, y)) => self.unset(x, y),
+            None => {},
+        }
+    }
+
+    pub fn get_f64(&self, x: f64, y: f64) -> Color {
+        round_point(x, y).map_or(Transparent, |(x, y)| self.get(x, y))
+    }
+
+    pub fn line_f64(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, c: Color) {
+        match (round_point(x1, y1), round_point(x2, y2)) {
+            (Some((x1, y1)), Some((x2, y2))) => self.line(x1, y1, x2, y2, c),
+            _ => {},
+        }
+    }
+
+    /// Returns the range of block coordinates to be drawn, as `(minx, miny, maxx, maxy)` where the
+    /// maxima are exclusive.
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        let (minx, miny, maxx, maxy) = self.blocks.extent().unwrap_or((0, 0, 0, 0));
+        (cmp::min(0, minx), cmp::min(0, miny),
+         cmp::max(self.width as i32, maxx), cmp::max(self.height as i32, maxy))
+    }
+
+    /// Appends row `y` of the canvas to `out`, only emitting escape sequences where the style
+    /// changes.
+    fn render_row(&self, y: i32, minx: i32, maxx: i32, out: &mut String) {
+        let default = Style::new(Transparent, Transparent);
+        let mut current = default;
+        for x in range(minx, maxx) {
+            let (style, c) = match self.blocks.find(x, y) {
+                Some(pixel) => pixel.resolve(self.depth),
+                None => (default, ' '),
+            };
+            out.push_str(transition(&current, &style, self.depth).as_slice());
+            out.push(c);
+            current = style;
+        }
+        if current != default {
+            out.push_str("\x1b[0m");
+        }
+    }
 
-            if ydiff != 0 {
-                y += (i * ydiff) / r * ydir;
+    /// Whether the block at the given block coordinates may be drawn to under the current
+    /// `Overflow` policy.
+    ///
+    /// The column at `i32::MAX` is never writable, so that the exclusive right-hand edge of the
+    /// drawn area can always be represented.
+    fn writable(&self, x: i32, y: i32) -> bool {
+        x != i32::MAX && (self.overflow == Expand ||
+            (x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32))
+    }
+}
+
+/// Canvases are equal if they have the same settings and draw the same pixels, however the pixels
+/// happen to be stored.
+impl PartialEq for Canvas {
+    fn eq(&self, other: &Canvas) -> bool {
+        if (self.width, self.height, self.overflow, self.depth) !=
+           (other.width, other.height, other.overflow, other.depth) {
+            return false;
+        }
+        let (minx, miny, maxx, maxy) = self.bounds();
+        if (minx, miny, maxx, maxy) != other.bounds() {
+            return false;
+        }
+        let blank = Default::default();
+        range(miny, maxy).all(|y| range(minx, maxx).all(|x| {
+            self.blocks.find(x, y).unwrap_or(&blank) == other.blocks.find(x, y).unwrap_or(&blank)
+        }))
+    }
+}
+
+impl Eq for Canvas {}
+
+/// An iterator over the rows of a `Canvas`, as returned by `Canvas::iter_rows`.
+pub struct Rows<'a> {
+    canvas: &'a Canvas,
+    y: i32,
+    minx: i32,
+    maxx: i32,
+    maxy: i32,
+}
+
+impl<'a> Iterator<String> for Rows<'a> {
+    fn next(&mut self) -> Option<String> {
+        if self.y >= self.maxy {
+            return None;
+        }
+        let mut row = String::new();
+        self.canvas.render_row(self.y, self.minx, self.maxx, &mut row);
+        self.y += 1;
+        Some(row)
+    }
+}
+
+/// Renders `Canvas`es into a buffer that is reused from one frame to the next.
+///
+/// Like `Canvas::rows`, this only emits escape sequences where the colours or attributes change,
+/// but it avoids allocating a new `String` for every row of every frame.
+pub struct Renderer {
+    buffer: String,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer { buffer: String::new() }
+    }
+
+    /// Renders the canvas in the same form as `Canvas::frame`, returning the renderer's buffer.
+    pub fn render<'a>(&'a mut self, canvas: &Canvas) -> &'a str {
+        self.buffer.truncate(0);
+        let (minx, miny, maxx, maxy) = canvas.bounds();
+        for y in range(miny, maxy) {
+            if y != miny {
+                self.buffer.push('\n');
             }
-            if xdiff != 0 {
-                x += (i * xdiff) / r * xdir;
+            canvas.render_row(y, minx, maxx, &mut self.buffer);
+        }
+        self.buffer.as_slice()
+    }
+
+    /// Renders the canvas in the same form as `Canvas::frame` and writes it to `out`.
+    pub fn render_to<W: Writer>(&mut self, canvas: &Canvas, out: &mut W) -> IoResult<()> {
+        out.write_str(self.render(canvas))
+    }
+}
+
+impl Render for Canvas {
+    fn rows(&self) -> Vec<String> {
+        self.rows()
+    }
+
+    fn cells(&self) -> Vec<Vec<String>> {
+        self.cells()
+    }
+
+    fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        self.render_to(out)
+    }
+}
+
+/// Formats the canvas in the same form as `Canvas::frame`, rendering one row at a time.
+impl Show for Canvas {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
+        for (i, row) in self.iter_rows().enumerate() {
+            if i != 0 {
+                try!(write!(fmt, "\n"));
             }
+            try!(write!(fmt, "{}", row));
+        }
+        Ok(())
+    }
+}
+
+impl Surface<Color> for Canvas {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.width, self.height * 2)
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, c: Color) {
+        self.set(x, y, c);
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> Color {
+        self.get(x, y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use term::{Monochrome, Colors8, Colors16, Colors256, TrueColor};
+    use super::{Canvas, Grid, Black, Red, Yellow, Blue, BrightRed, Transparent, Indexed, Rgb};
+
+    fn canvas() -> Canvas {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set_color_depth(TrueColor);
+        canvas
+    }
 
-            result.push((x, y));
+    #[test]
+    fn quantise_keeps_displayable_colours() {
+        for &depth in [Monochrome, Colors8, Colors16, Colors256, TrueColor].iter() {
+            assert_eq!(Transparent.quantise(depth), Transparent);
+            assert_eq!(Red.quantise(depth), Red);
         }
-        result
+        assert_eq!(Rgb(1, 2, 3).quantise(TrueColor), Rgb(1, 2, 3));
+        assert_eq!(Rgb(1, 2, 3).quantise(Monochrome), Rgb(1, 2, 3));
+        assert_eq!(Indexed(100).quantise(Colors256), Indexed(100));
+        assert_eq!(BrightRed.quantise(Colors16), BrightRed);
     }
 
-    pub fn line(&mut self, x1: uint, y1: uint, x2: uint, y2: uint, c: Color) {
-        for &(x, y) in self.line_vec(x1, y1, x2, y2).iter() {
-            self.set(x, y, c);
+    #[test]
+    fn quantise_picks_the_nearest_colour() {
+        assert_eq!(Rgb(255, 0, 0).quantise(Colors256), Indexed(196));
+        assert_eq!(Rgb(128, 128, 128).quantise(Colors256), Indexed(244));
+        assert_eq!(Rgb(255, 0, 0).quantise(Colors16), BrightRed);
+        assert_eq!(Indexed(9).quantise(Colors16), BrightRed);
+        assert_eq!(Indexed(196).quantise(Colors16), BrightRed);
+        assert_eq!(BrightRed.quantise(Colors8), Red);
+        assert_eq!(Indexed(3).quantise(Colors8), Yellow);
+        assert_eq!(Indexed(12).quantise(Colors8), Blue);
+        assert_eq!(Rgb(0, 0, 0).quantise(Colors8), Black);
+    }
+
+    #[test]
+    fn unset_pixels_show_the_background() {
+        let mut canvas = canvas();
+        canvas.set(0, 1, Red);
+        canvas.unset(0, 1);
+        assert_eq!(canvas.rows(), vec!["  ".to_string()]);
+    }
+
+    #[test]
+    fn huge_canvases_use_sparse_storage() {
+        let mut canvas = Canvas::new(4096, 2100);
+        canvas.set(4000, 2000, Red);
+        assert_eq!(canvas.get(4000, 2000), Red);
+        assert!(Canvas::try_new(1 << 31, 2).is_err());
+    }
+
+    #[test]
+    fn empty_grids_grow_with_slack() {
+        let mut grid = Grid::new(0, 0);
+        assert!(grid.reserve(0, 0));
+        let (width, height) = (grid.width, grid.height);
+        assert!(width > 1 && height > 1);
+        assert!(grid.reserve(1, 1));
+        assert_eq!((grid.width, grid.height), (width, height));
+    }
+
+    #[test]
+    fn equality_ignores_storage() {
+        let (mut first, mut second) = (Canvas::new(2, 2), Canvas::new(2, 2));
+        first.set(-5, 0, Red);
+        first.set(-1, 0, Blue);
+        second.set(-1, 0, Blue);
+        second.set(-5, 0, Red);
+        assert!(first == second);
+
+        // Drawing far away switches the dense canvas to sparse storage.
+        fn draw(canvas: &mut Canvas) {
+            canvas.set(1, 1, Red);
+            canvas.set(100000, 100000, Blue);
         }
+        let (mut dense, mut sparse) = (Canvas::new(2, 2), Canvas::new_sparse(2, 2));
+        draw(&mut dense);
+        draw(&mut sparse);
+        assert!(dense == sparse);
+        dense.set(1, 1, Blue);
+        assert!(dense != sparse);
+    }
+
+    #[test]
+    fn formats_as_the_frame() {
+        let mut canvas = canvas();
+        canvas.set(0, 0, Red);
+        canvas.set(1, 3, Blue);
+        assert_eq!(format!("{}", canvas), canvas.frame());
+    }
+
+    #[test]
+    fn half_set_cells_leave_the_other_half_blank() {
+        let mut canvas = canvas();
+        canvas.set(0, 0, Red);
+        canvas.set(1, 1, Blue);
+        assert_eq!(canvas.rows(), vec!["\x1b[31m▀\x1b[34m▄\x1b[0m".to_string()]);
     }
 }
@@ -1,35 +1,131 @@
 //! Terminal graphics using Braille characters
 //!
 //! This module provides an interface for utilising Braille characters to draw a picture to a
-//! terminal, allowing for much smaller pixels but losing proper colour support.
+//! terminal, allowing for much smaller pixels but losing proper colour support: colours can only
+//! be given to whole cells of two by four pixels, not to individual pixels.
+//!
+//! Text can be written over the pixels with `Canvas::text`, one character to each cell (or two
+//! cells, for wide characters such as CJK ideographs and emoji).
 
 use std::char;
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt::{Show, Formatter, FormatError};
+use std::io::{IoResult, Writer};
+
+use block::{Color, Rgb, Transparent};
+use shapes;
+use surface::{Render, Surface};
+use term::{ColorDepth, Monochrome};
+use super::{Error, OutOfBounds, Overflow, Expand};
+use super::{check_dimensions, div_floor, mod_floor, round_point};
+
 static PIXEL_MAP: [[int, ..2], ..4] = [[0x01, 0x08],
                                        [0x02, 0x10],
                                        [0x04, 0x20],
                                        [0x40, 0x80]];
 
+/// How the foreground colour of a cell is chosen when pixels of different colours are drawn in
+/// it.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum ColorMerge {
+    /// The cell takes the colour of the most recently drawn pixel.
+    LastWins,
+    /// The cell keeps the colour of the first coloured pixel drawn in it.
+    FirstWins,
+    /// The cell's colour is the average of the colours of the pixels drawn in it.
+    Blend,
+}
+
+/// A single Braille character on the canvas, or the text character drawn over it.
+#[deriving(Clone, PartialEq, Eq)]
+struct Cell {
+    dots: int,
+    text: Option<char>,
+    fg: Option<Color>,
+    bg: Option<Color>,
+}
+
+impl Cell {
+    fn new() -> Cell {
+        Cell { dots: 0, text: None, fg: None, bg: None }
+    }
+
+    /// The number of terminal columns the cell takes up.
+    fn span(&self) -> i32 {
+        match self.text {
+            Some(c) => char_width(c) as i32,
+            None => 1,
+        }
+    }
+}
+
 /// A canvas object that can be used to draw to the terminal using Braille characters.
 #[deriving(Clone, PartialEq, Eq)]
 pub struct Canvas {
-    chars: Vec<int>,
+    chars: HashMap<(i32, i32), Cell>,
     width:  uint,
     height: uint,
+    blank:  char,
+    overflow: Overflow,
+    merge: ColorMerge,
+    depth: ColorDepth,
 }
 
 impl Canvas {
     /// Creates a new `Canvas` with the given width and height.
     ///
-    /// Note that the `Canvas` can still draw outside the given dimensions (expanding the canvas)
-    /// if a pixel is set outside the dimensions.
+    /// Note that by default the `Canvas` can still draw outside the given dimensions (expanding the
+    /// canvas) if a pixel is set outside the dimensions; see `Canvas::set_overflow`.
+    ///
+    /// Fails if the dimensions are too large to be addressed; see `Canvas::try_new`.
     pub fn new(width: uint, height: uint) -> Canvas {
-        Canvas {
-            chars: Vec::new(),
-            width: width / 2,
-            height: height / 4,
-        }
+        Canvas::try_new(width, height).unwrap()
+    }
+
+    /// Creates a new `Canvas` like `Canvas::new`, returning an error if the dimensions are too
+    /// large to be addressed with `i32` coordinates.
+    pub fn try_new(width: uint, height: uint) -> Result<Canvas, Error> {
+        try!(check_dimensions(width, height));
+        Ok(Canvas {
+            chars: HashMap::new(),
+            width: (width + 1) / 2,
+            height: (height + 3) / 4,
+            blank: '\u2800',
+            overflow: Expand,
+            merge: LastWins,
+            depth: ColorDepth::detect(),
+        })
+    }
+
+    /// Sets the character used to render cells with no pixels set.
+    ///
+    /// This defaults to the empty Braille pattern (U+2800), which keeps every row the same width
+    /// in the terminal; a space (`' '`) may be preferable when the output is to be copied as text.
+    pub fn set_blank(&mut self, c: char) {
+        self.blank = c;
+    }
+
+    /// Sets how pixels outside of the dimensions given to `Canvas::new` are treated.
+    ///
+    /// This only affects pixels drawn after the call; anything already on the canvas is kept.
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    /// Sets how the colour of a cell is chosen when differently coloured pixels are drawn in it.
+    ///
+    /// This defaults to `LastWins`.
+    pub fn set_color_merge(&mut self, merge: ColorMerge) {
+        self.merge = merge;
+    }
+
+    /// Sets the colour depth that output is restricted to.
+    ///
+    /// This defaults to the depth detected from the environment by `ColorDepth::detect`; see
+    /// `block::Canvas::set_color_depth`.
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.depth = depth;
     }
 
     /// Clears the canvas.
@@ -37,46 +133,525 @@ impl Canvas {
         self.chars.clear();
     }
 
+    /// Writes text onto the canvas, starting at the given cell coordinates.
+    ///
+    /// Each character occupies a whole cell of two by four pixels, so `x` and `y` count cells
+    /// rather than pixels. Wide characters, such as CJK ideographs and emoji, occupy two cells.
+    /// Text is drawn in place of any pixels set in its cells, which are kept and shown again if the
+    /// text is removed with `Canvas::remove_text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drawille::braille::Canvas;
+    /// use drawille::term::Monochrome;
+    ///
+    /// let mut canvas = Canvas::new(10, 4);
+    /// canvas.set_blank(' ');
+    /// canvas.set_color_depth(Monochrome);
+    /// canvas.text(0, 0, "日本");
+    /// canvas.text(4, 0, "!");
+    /// assert_eq!(canvas.frame(), "日本!".to_string());
+    /// ```
+    pub fn text<S: Str>(&mut self, x: i32, y: i32, s: S) {
+        self.write_text(x, y, None, s.as_slice());
+    }
+
+    /// Writes text onto the canvas like `Canvas::text`, colouring its cells with `fg`.
+    ///
+    /// The colour replaces that of any pixels in the cells, regardless of the `ColorMerge`
+    /// policy.
+    pub fn text_colored<S: Str>(&mut self, x: i32, y: i32, fg: Color, s: S) {
+        self.write_text(x, y, Some(fg), s.as_slice());
+    }
+
+    /// Removes any text from the cell at the given cell coordinates, including a wide character
+    /// that begins in the cell to its left.
+    pub fn remove_text(&mut self, x: i32, y: i32) {
+        if self.is_tail((x, y)) {
+            self.clear_text((x - 1, y));
+        }
+        self.clear_text((x, y));
+    }
+
     /// Sets a pixel at the specified coordinates.
-    pub fn set(&mut self, x: uint, y: uint) {
-        let (row, col) = (x / 2, y / 4);
-        let index = row*self.width + col;
-        *self.chars.get_mut(index) |= PIXEL_MAP[y % 4][x % 2];
+    pub fn set(&mut self, x: i32, y: i32) {
+        let _ = self.try_set(x, y);
+    }
+
+    /// Sets a pixel at the specified coordinates, failing if it is outside of a canvas that
+    /// doesn't expand.
+    pub fn try_set(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        let (cell, dot) = locate(x, y);
+        if !self.writable(cell) {
+            return Err(OutOfBounds(x, y));
+        }
+        self.chars.find_or_insert(cell, Cell::new()).dots |= dot;
+        Ok(())
+    }
+
+    /// Sets a pixel at the specified coordinates and colours its cell with `fg`.
+    ///
+    /// If the cell already has a colour, the new colour is chosen according to the canvas's
+    /// `ColorMerge` policy.
+    pub fn set_colored(&mut self, x: i32, y: i32, fg: Color) {
+        let (cell, dot) = locate(x, y);
+        if !self.writable(cell) {
+            return;
+        }
+        let merge = self.merge;
+        let cell = self.chars.find_or_insert(cell, Cell::new());
+        let others = count_dots(cell.dots & !dot);
+        cell.fg = Some(match (cell.fg, merge) {
+            (None, _) | (Some(_), LastWins) => fg,
+            (Some(old), FirstWins) => old,
+            (Some(old), Blend) => blend(old, fg, others),
+        });
+        cell.dots |= dot;
+    }
+
+    /// Sets the background colour of the cell containing the pixel at the specified coordinates.
+    ///
+    /// Passing `None` restores the terminal's own background colour.
+    pub fn set_cell_background(&mut self, x: i32, y: i32, bg: Option<Color>) {
+        let (cell, _) = locate(x, y);
+        if !self.writable(cell) {
+            return;
+        }
+        self.chars.find_or_insert(cell, Cell::new()).bg = bg;
+        self.remove_if_empty(cell);
+    }
+
+    /// Returns the foreground and background colours of the cell containing the pixel at the
+    /// specified coordinates.
+    pub fn get_colors(&self, x: i32, y: i32) -> (Option<Color>, Option<Color>) {
+        let (cell, _) = locate(x, y);
+        match self.chars.find(&cell) {
+            None => (None, None),
+            Some(c) => (c.fg, c.bg),
+        }
     }
 
     /// Deletes a pixel at the specified coordinates.
-    pub fn unset(&mut self, x: uint, y: uint) {
-        let (row, col) = (x / 2, y / 4);
-        let index = row*self.width + col;
-        *self.chars.get_mut(index) &= PIXEL_MAP[y % 4][x % 2];
+    pub fn unset(&mut self, x: i32, y: i32) {
+        let _ = self.try_unset(x, y);
+    }
+
+    /// Deletes a pixel at the specified coordinates, failing if it is outside of a canvas that
+    /// doesn't expand.
+    pub fn try_unset(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        let (cell, dot) = locate(x, y);
+        if !self.writable(cell) {
+            return Err(OutOfBounds(x, y));
+        }
+        match self.chars.find_mut(&cell) {
+            None => return Ok(()),
+            Some(c) => {
+                c.dots &= !dot;
+                if c.dots == 0 && c.text.is_none() {
+                    c.fg = None;
+                }
+            }
+        }
+        self.remove_if_empty(cell);
+        Ok(())
     }
 
     /// Toggles a pixel at the specified coordinates.
-    pub fn toggle(&mut self, x: uint, y: uint) {
-        let (row, col) = (x / 2, y / 4);
-        let index = row*self.width + col;
-        *self.chars.get_mut(index) ^= PIXEL_MAP[y % 4][x % 2];
+    pub fn toggle(&mut self, x: i32, y: i32) {
+        let _ = self.try_toggle(x, y);
+    }
+
+    /// Toggles a pixel at the specified coordinates, failing if it is outside of a canvas that
+    /// doesn't expand.
+    pub fn try_toggle(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        if try!(self.try_get(x, y)) {
+            self.try_unset(x, y)
+        } else {
+            self.try_set(x, y)
+        }
     }
 
     /// Detects whether the pixel at the given coordinates is set.
-    pub fn get(&self, x: uint, y: uint) -> bool {
-        let dot_index = PIXEL_MAP[y % 4][x % 2];
-        let (row, col) = (x / 2, y / 4);
-        let index = row*self.width + col;
-        let c = self.chars[index];
-        return c & dot_index != 0;
+    ///
+    /// Pixels that have never been drawn to are reported as unset.
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        self.try_get(x, y).unwrap_or(false)
+    }
+
+    /// Detects whether the pixel at the given coordinates is set, failing if it is outside of a
+    /// canvas that doesn't expand.
+    pub fn try_get(&self, x: i32, y: i32) -> Result<bool, Error> {
+        let (cell, dot) = locate(x, y);
+        if !self.writable(cell) {
+            return Err(OutOfBounds(x, y));
+        }
+        match self.chars.find(&cell) {
+            None => Ok(false),
+            Some(c) => Ok(c.dots & dot != 0),
+        }
+    }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` onto the `Canvas`.
+    pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        for (x, y) in shapes::line_points(x1, y1, x2, y2) {
+            self.set(x, y);
+        }
+    }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` in the given colour.
+    pub fn line_colored(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, fg: Color) {
+        for (x, y) in shapes::line_points(x1, y1, x2, y2) {
+            self.set_colored(x, y, fg);
+        }
+    }
+
+    /// Sets the pixel nearest to the given floating-point coordinates.
+    pub fn set_f64(&mut self, x: f64, y: f64) {
+        match round_point(x, y) {
+            Some((x, y)) => self.set(x, y),
+            None => {},
+        }
+    }
+
+    /// Deletes the pixel nearest to the given floating-point coordinates.
+    pub fn unset_f64(&mut self, x: f64, y: f64) {
+        match round_point(x, y) {
+            Some((x, y)) => self.unset(x, y),
+            None => {},
+        }
+    }
+
+    /// Toggles the pixel nearest to the given floating-point coordinates.
+    pub fn toggle_f64(&mut self, x: f64, y: f64) {
+        match round_point(x, y) {
+            Some((x, y)) => self.toggle(x, y),
+            None => {},
+        }
+    }
+
+    /// Detects whether the pixel nearest to the given floating-point coordinates is set.
+    pub fn get_f64(&self, x: f64, y: f64) -> bool {
+        round_point(x, y).map_or(false, |(x, y)| self.get(x, y))
+    }
+
+    /// Draws a line between the pixels nearest to the given floating-point coordinates.
+    pub fn line_f64(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        match (round_point(x1, y1), round_point(x2, y2)) {
+            (Some((x1, y1)), Some((x2, y2))) => self.line(x1, y1, x2, y2),
+            _ => {},
+        }
+    }
+
+    /// Returns a `Vec` of each row of the `Canvas`.
+    ///
+    /// Note that each row is actually four pixels high due to the fact that a single Braille
+    /// character spans two by four pixels.
+    ///
+    /// The rows cover the dimensions given to `Canvas::new`, extended as necessary to include every
+    /// pixel that has been drawn outside of them.
+    pub fn rows(&self) -> Vec<String> {
+        self.iter_rows().collect()
+    }
+
+    /// Returns an iterator over the rows of the `Canvas`, which renders each row only as it is
+    /// reached.
+    pub fn iter_rows<'a>(&'a self) -> Rows<'a> {
+        let (minx, miny, maxx, maxy) = self.bounds();
+        Rows { canvas: self, y: miny, minx: minx, maxx: maxx, maxy: maxy }
+    }
+
+    /// Returns the character in each cell of the `Canvas`, as a `Vec` of rows.
+    ///
+    /// This covers the same area as `Canvas::rows`. The cell covered by the second half of a wide
+    /// character is given as an empty string, so that each cell stays in its own column.
+    pub fn cells(&self) -> Vec<Vec<String>> {
+        let (minx, miny, maxx, maxy) = self.bounds();
+
+        let mut result = vec![];
+        for y in range(miny, maxy) {
+            let mut row = vec![];
+            for x in range(minx, maxx) {
+                let mut cell = String::new();
+                if !self.is_tail((x, y)) {
+                    self.render_cell(self.chars.find(&(x, y)), &mut cell);
+                }
+                row.push(cell);
+            }
+            result.push(row);
+        }
+        result
+    }
+
+    /// Draws the canvas to a `String` and returns it.
+    pub fn frame(&self) -> String {
+        self.rows().connect("\n")
+    }
+
+    /// Draws the canvas to `out` in the same form as `Canvas::frame`.
+    ///
+    /// Only a single row is held in memory at a time, so this is suitable for streaming large
+    /// canvases to files or sockets.
+    pub fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        let (minx, miny, maxx, maxy) = self.bounds();
+        let mut row = String::new();
+        for y in range(miny, maxy) {
+            if y != miny {
+                try!(out.write_str("\n"));
+            }
+            row.truncate(0);
+            self.render_row(y, minx, maxx, &mut row);
+            try!(out.write_str(row.as_slice()));
+        }
+        Ok(())
+    }
+
+    /// Returns the range of cells to be drawn, as `(minx, miny, maxx, maxy)` where the maxima are
+    /// exclusive.
+    ///
+    /// A wide character in the last column extends the range to cover both of its cells.
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        let minx = cmp::min(0, self.chars.keys().map(|&(x, _)| x).min().unwrap_or(0));
+        let miny = cmp::min(0, self.chars.keys().map(|&(_, y)| y).min().unwrap_or(0));
+        let maxx = cmp::max(self.width as i32,
+                            self.chars.iter().map(|(&(x, _), c)| x + c.span()).max().unwrap_or(0));
+        let maxy = cmp::max(self.height as i32,
+                            self.chars.keys().map(|&(_, y)| y + 1).max().unwrap_or(0));
+        (minx, miny, maxx, maxy)
+    }
+
+    fn render_row(&self, y: i32, minx: i32, maxx: i32, out: &mut String) {
+        for x in range(minx, maxx) {
+            if !self.is_tail((x, y)) {
+                self.render_cell(self.chars.find(&(x, y)), out);
+            }
+        }
+    }
+
+    fn write_text(&mut self, x: i32, y: i32, fg: Option<Color>, s: &str) {
+        let mut col = x;
+        for c in s.chars() {
+            let width = char_width(c) as i32;
+            if width == 0 {
+                continue;
+            }
+            // A wide character is only drawn if both of its cells can be.
+            if range(col, col + width).all(|col| self.writable((col, y))) {
+                for col in range(col, col + width) {
+                    self.remove_text(col, y);
+                }
+                let cell = self.chars.find_or_insert((col, y), Cell::new());
+                cell.text = Some(c);
+                if fg.is_some() {
+                    cell.fg = fg;
+                }
+            }
+            col += width;
+        }
+    }
+
+    /// Removes the text from a single cell.
+    fn clear_text(&mut self, cell: (i32, i32)) {
+        match self.chars.find_mut(&cell) {
+            Some(c) => {
+                c.text = None;
+                if c.dots == 0 {
+                    c.fg = None;
+                }
+            }
+            None => return,
+        }
+        self.remove_if_empty(cell);
+    }
+
+    /// Whether the given cell is covered by a wide character in the cell to its left, and so isn't
+    /// drawn itself.
+    fn is_tail(&self, (col, row): (i32, i32)) -> bool {
+        match self.chars.find(&(col - 1, row)) {
+            Some(c) => c.span() == 2,
+            None => false,
+        }
+    }
+
+    /// Whether the given cell may be drawn to under the current `Overflow` policy.
+    fn writable(&self, (col, row): (i32, i32)) -> bool {
+        self.overflow == Expand ||
+            (col >= 0 && row >= 0 && col < self.width as i32 && row < self.height as i32)
+    }
+
+    /// Removes a cell from the canvas if it has nothing to show, so that it no longer extends the
+    /// canvas.
+    fn remove_if_empty(&mut self, cell: (i32, i32)) {
+        let empty = match self.chars.find(&cell) {
+            Some(c) => c.dots == 0 && c.text.is_none() && c.bg.is_none(),
+            None => false,
+        };
+        if empty {
+            self.chars.remove(&cell);
+        }
+    }
+
+    fn render_cell(&self, cell: Option<&Cell>, out: &mut String) {
+        let cell = match cell {
+            None => return out.push(self.blank),
+            Some(cell) => cell,
+        };
+        let glyph = cell.text.unwrap_or_else(|| self.glyph(cell.dots));
+        if self.depth == Monochrome {
+            return out.push(glyph);
+        }
+
+        let mut params = vec![];
+        match cell.fg {
+            Some(c) => params.push(c.quantise(self.depth).sgr(false)),
+            None => {},
+        }
+        match cell.bg {
+            Some(c) => params.push(c.quantise(self.depth).sgr(true)),
+            None => {},
+        }
+        if params.is_empty() {
+            out.push(glyph);
+        } else {
+            out.push_str(format!("\x1b[{}m{}\x1b[0m", params.connect(";"), glyph).as_slice());
+        }
+    }
+
+    fn glyph(&self, dots: int) -> char {
+        if dots == 0 {
+            self.blank
+        } else {
+            char::from_u32(0x2800 + dots as u32).unwrap()
+        }
+    }
+}
+
+/// Returns the cell containing the pixel at `(x, y)` along with the pixel's bit within that cell.
+fn locate(x: i32, y: i32) -> ((i32, i32), int) {
+    let cell = (div_floor(x, 2), div_floor(y, 4));
+    (cell, PIXEL_MAP[mod_floor(y, 4) as uint][mod_floor(x, 2) as uint])
+}
+
+/// Returns the number of terminal columns taken up by a character: 0 for combining and other
+/// zero-width characters, 2 for East Asian wide and fullwidth characters and most emoji, and 1
+/// otherwise.
+fn char_width(c: char) -> uint {
+    match c as u32 {
+        0x0300...0x036F | 0x200B...0x200F | 0xFE00...0xFE0F | 0xFE20...0xFE2F => 0,
+        0x1100...0x115F | 0x2E80...0x303E | 0x3041...0x33FF | 0x3400...0x4DBF |
+        0x4E00...0x9FFF | 0xA000...0xA4CF | 0xAC00...0xD7A3 | 0xF900...0xFAFF |
+        0xFE30...0xFE4F | 0xFF00...0xFF60 | 0xFFE0...0xFFE6 | 0x1F300...0x1F64F |
+        0x1F680...0x1F6FF | 0x1F900...0x1F9FF | 0x20000...0x2FFFD | 0x30000...0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Returns the number of pixels set in a cell.
+fn count_dots(dots: int) -> uint {
+    range(0u, 8).filter(|&i| dots & (1 << i) != 0).count()
+}
+
+/// Mixes the colour `new` into `old`, where `old` is the colour of `weight` pixels.
+///
+/// Colours are only mixed when they differ, so that a cell drawn in one palette colour keeps it.
+/// `Transparent` has no colour of its own, so mixing it with another colour gives that colour.
+fn blend(old: Color, new: Color, weight: uint) -> Color {
+    match (old, new) {
+        _ if weight == 0 || old == new => new,
+        (Transparent, _) => new,
+        (_, Transparent) => old,
+        _ => {
+            let ((r1, g1, b1), (r2, g2, b2)) = (old.rgb(), new.rgb());
+            let mix = |a: u8, b: u8| ((a as uint * weight + b as uint) / (weight + 1)) as u8;
+            Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+    }
+}
+
+/// An iterator over the rows of a `Canvas`, as returned by `Canvas::iter_rows`.
+pub struct Rows<'a> {
+    canvas: &'a Canvas,
+    y: i32,
+    minx: i32,
+    maxx: i32,
+    maxy: i32,
+}
+
+impl<'a> Iterator<String> for Rows<'a> {
+    fn next(&mut self) -> Option<String> {
+        if self.y >= self.maxy {
+            return None;
+        }
+        let mut row = String::new();
+        self.canvas.render_row(self.y, self.minx, self.maxx, &mut row);
+        self.y += 1;
+        Some(row)
+    }
+}
+
+impl Render for Canvas {
+    fn rows(&self) -> Vec<String> {
+        self.rows()
+    }
+
+    fn cells(&self) -> Vec<Vec<String>> {
+        self.cells()
+    }
+
+    fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        self.render_to(out)
+    }
+}
+
+impl Surface<bool> for Canvas {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.width * 2, self.height * 4)
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, p: bool) {
+        if p {
+            self.set(x, y);
+        } else {
+            self.unset(x, y);
+        }
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> bool {
+        self.get(x, y)
     }
 }
 
-//printf("%c[2J",27);
 impl Show for Canvas {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
-        for (i, c) in self.chars.iter().enumerate() {
-            if i % self.width == 0 {
-                try!(write!(fmt,"\n"));
+        for (i, row) in self.iter_rows().enumerate() {
+            if i != 0 {
+                try!(write!(fmt, "\n"));
             }
-            try!(write!(fmt, "{}", *c as int));
+            try!(write!(fmt, "{}", row));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use block::{Red, Blue, Rgb, Transparent};
+    use super::blend;
+
+    #[test]
+    fn blend_keeps_matching_colours() {
+        assert_eq!(blend(Red, Red, 3), Red);
+        assert_eq!(blend(Red, Blue, 0), Blue);
+    }
+
+    #[test]
+    fn blend_ignores_transparent() {
+        assert_eq!(blend(Transparent, Red, 2), Red);
+        assert_eq!(blend(Red, Transparent, 2), Red);
+    }
+
+    #[test]
+    fn blend_mixes_by_weight() {
+        assert_eq!(blend(Rgb(0, 0, 0), Rgb(90, 30, 3), 2), Rgb(30, 10, 1));
+    }
+}
@@ -0,0 +1,155 @@
+//! Incremental redrawing of canvases
+//!
+//! Rather than writing out every cell of a frame, the functions in this module compare a frame
+//! against the one previously drawn and only redraw the cells that have changed, moving the cursor
+//! directly to each of them. When so much has changed that this would produce more output than
+//! simply drawing the whole frame again, the whole frame is drawn instead.
+//!
+//! Frames are assumed to be drawn with their top-left corner in the top-left corner of the
+//! terminal, as the `animate` module does.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::MemWriter;
+//! use drawille::braille::Canvas;
+//! use drawille::diff;
+//!
+//! let old = Canvas::new(10, 8);
+//! let mut new = old.clone();
+//! new.set(3, 3);
+//!
+//! let mut out = MemWriter::new();
+//! diff::render_diff(&mut out, &old, &new).unwrap();
+//! ```
+
+use std::io::{IoResult, Writer};
+
+use surface::Render;
+
+static CURSOR_HOME: &'static str = "\x1b[H";
+static RESET: &'static str = "\x1b[0m";
+static CLEAR_LINE: &'static str = "\x1b[K";
+static CLEAR_BELOW: &'static str = "\x1b[J";
+
+/// Returns the `(column, row)` coordinates of every cell that differs between two grids of cells,
+/// as returned by `Render::cells`.
+///
+/// If the grids are of different sizes, every cell of `new` is considered changed.
+///
+/// # Example
+///
+/// ```
+/// use drawille::diff::changed_cells;
+///
+/// let old = vec![vec!["a".to_string(), "b".to_string()],
+///                vec!["c".to_string(), "d".to_string()]];
+/// let mut new = old.clone();
+/// *new.get_mut(1).get_mut(0) = "x".to_string();
+/// assert_eq!(changed_cells(old.as_slice(), new.as_slice()), vec![(0, 1)]);
+/// assert_eq!(changed_cells(old.as_slice(), old.as_slice()), vec![]);
+/// assert_eq!(changed_cells(old.slice_to(1), new.as_slice()).len(), 4);
+/// ```
+pub fn changed_cells(old: &[Vec<String>], new: &[Vec<String>]) -> Vec<(uint, uint)> {
+    let same_size = old.len() == new.len() &&
+        old.iter().zip(new.iter()).all(|(a, b)| a.len() == b.len());
+
+    let mut result = vec![];
+    for (y, row) in new.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if !same_size || old[y][x] != *cell {
+                result.push((x, y));
+            }
+        }
+    }
+    result
+}
+
+/// Redraws the cells of `old` that differ in `new`, or all of `new` if that is shorter.
+pub fn render_diff<R: Render, W: Writer>(out: &mut W, old: &R, new: &R) -> IoResult<()> {
+    write_diff(out, old.cells().as_slice(), new.cells().as_slice(), new.rows().as_slice())
+}
+
+/// Draws the whole of `new` over whatever is currently on the terminal.
+pub fn render_full<R: Render, W: Writer>(out: &mut W, new: &R) -> IoResult<()> {
+    out.write_str(full(new.rows().as_slice()).as_slice())
+}
+
+/// Redraws the cells of the grid `old` that differ in `new`, or all of `new` if that is shorter.
+///
+/// Grids are as returned by `Render::cells`, and `rows` must be the same frame as `new`, as
+/// returned by `Render::rows`, which is drawn when redrawing the whole frame. If the grids are of
+/// different sizes, the whole of `new` is drawn, erasing anything left over from `old`.
+pub fn write_diff<W: Writer>(out: &mut W, old: &[Vec<String>], new: &[Vec<String>],
+                             rows: &[String]) -> IoResult<()> {
+    let full = full(rows);
+    let mut changes = String::new();
+    let mut cursor = None;
+    for &(x, y) in changed_cells(old, new).iter() {
+        // Writing a cell moves the cursor one cell along, so runs of changed cells only need the
+        // cursor to be placed once.
+        if cursor != Some((x, y)) {
+            changes.push_str(format!("\x1b[{};{}H", y + 1, x + 1).as_slice());
+        }
+        changes.push_str(new[y][x].as_slice());
+        cursor = Some((x + 1, y));
+        if changes.len() >= full.len() {
+            return out.write_str(full.as_slice());
+        }
+    }
+    if !changes.is_empty() {
+        changes.push_str(RESET);
+    }
+    out.write_str(changes.as_slice())
+}
+
+/// Returns the output that redraws the whole of a frame from the top-left corner, given its rows
+/// as returned by `Render::rows`, which each restore the terminal's default colours at their end.
+fn full(rows: &[String]) -> String {
+    let mut result = String::from_str(CURSOR_HOME);
+    for (i, row) in rows.iter().enumerate() {
+        if i != 0 {
+            result.push_str("\n");
+        }
+        result.push_str(row.as_slice());
+        result.push_str(CLEAR_LINE);
+    }
+    result.push_str(CLEAR_BELOW);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::MemWriter;
+    use std::str;
+
+    use block::{Canvas, Red, Blue};
+    use term::TrueColor;
+    use super::{render_diff, render_full};
+
+    fn output(f: |&mut MemWriter|) -> String {
+        let mut out = MemWriter::new();
+        f(&mut out);
+        str::from_utf8(out.get_ref()).unwrap().to_string()
+    }
+
+    #[test]
+    fn full_redraws_use_the_rows() {
+        let mut canvas = Canvas::new(3, 4);
+        canvas.set_color_depth(TrueColor);
+        canvas.line(0, 0, 2, 3, Red);
+        let rows = canvas.rows();
+        let expected = format!("\x1b[H{}\x1b[K\n{}\x1b[K\x1b[J", rows[0], rows[1]);
+        assert_eq!(output(|out| render_full(out, &canvas).unwrap()), expected);
+
+        // Redrawing every cell falls back to the same output.
+        let mut other = canvas.clone();
+        for x in range(0, 3) {
+            for y in range(0, 4) {
+                other.set(x, y, Blue);
+            }
+        }
+        let full = output(|out| render_full(out, &other).unwrap());
+        assert_eq!(output(|out| render_diff(out, &canvas, &other).unwrap()), full);
+    }
+}
@@ -18,6 +18,111 @@
 //!     println!("{}", canvas.frame());
 //! }
 //! ```
+//!
+//! # Floating-point coordinates
+//!
+//! Both canvases accept `f64` coordinates through the `_f64` variants of their drawing methods.
+//! These are rounded to the nearest pixel, with coordinates exactly halfway between two pixels
+//! always rounded up (towards positive infinity). Because the rule doesn't depend on the sign of
+//! the coordinate, two segments sharing an endpoint always meet at the same pixel.
+//!
+//! An infinite or NaN coordinate, such as the logarithm of a zero sample, doesn't name any pixel,
+//! so drawing at it does nothing and pixels at it read as unset; a line or stroke with such an
+//! end isn't drawn at all. Finite coordinates beyond the range of `i32` are clamped to the nearest
+//! `i32`, but a line to such a point still passes through every pixel on the way, so coordinates
+//! that may be huge are best clipped to the area of interest first.
 
+use std::i32;
+use std::io::IoError;
+
+pub mod animate;
 pub mod braille;
 pub mod block;
+pub mod diff;
+pub mod font;
+pub mod hershey;
+pub mod image;
+#[cfg(feature = "image")]
+mod pnm;
+pub mod shapes;
+pub mod surface;
+pub mod term;
+pub mod turtle;
+
+pub use surface::{Render, Surface};
+
+/// The errors that can occur when drawing or loading images and fonts.
+///
+/// These are returned by the `try_` variants of canvas methods; the other methods instead ignore
+/// whatever they can't do.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The pixel at the given coordinates lies outside a canvas that doesn't expand.
+    OutOfBounds(i32, i32),
+    /// The pixel at the given coordinates is in a cell that holds text rather than pixels.
+    TextCell(i32, i32),
+    /// A canvas can't be created with the given width and height.
+    InvalidDimensions(uint, uint),
+    /// An image couldn't be decoded, for the given reason.
+    InvalidImage(String),
+    /// A font couldn't be parsed, for the given reason.
+    InvalidFont(String),
+    /// An image or font couldn't be read.
+    Io(IoError),
+}
+
+/// How a canvas treats pixels drawn outside of the dimensions it was created with.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    /// Pixels outside the canvas are silently discarded.
+    Clip,
+    /// The canvas grows to include any pixel drawn outside of it, including at negative
+    /// coordinates.
+    Expand,
+}
+
+/// Checks that a canvas of the given dimensions can be addressed with `i32` coordinates.
+fn check_dimensions(width: uint, height: uint) -> Result<(), Error> {
+    if width > i32::MAX as uint || height > i32::MAX as uint {
+        Err(InvalidDimensions(width, height))
+    } else {
+        Ok(())
+    }
+}
+
+/// Rounds a floating-point coordinate to a pixel, as described in the crate documentation,
+/// returning `None` for infinities and NaN.
+fn round_coord(v: f64) -> Option<i32> {
+    if !v.is_finite() {
+        return None;
+    }
+    let r = (v + 0.5).floor();
+    Some(if r <= i32::MIN as f64 {
+        i32::MIN
+    } else if r >= i32::MAX as f64 {
+        i32::MAX
+    } else {
+        r as i32
+    })
+}
+
+/// Rounds a pair of floating-point coordinates with `round_coord`, returning `None` if either is
+/// infinite or NaN.
+fn round_point(x: f64, y: f64) -> Option<(i32, i32)> {
+    match (round_coord(x), round_coord(y)) {
+        (Some(x), Some(y)) => Some((x, y)),
+        _ => None,
+    }
+}
+
+/// Divides `a` by `b`, rounding towards negative infinity rather than towards zero.
+fn div_floor(a: i32, b: i32) -> i32 {
+    let d = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { d - 1 } else { d }
+}
+
+/// The remainder of `div_floor(a, b)`, which always has the same sign as `b`.
+fn mod_floor(a: i32, b: i32) -> i32 {
+    let r = a % b;
+    if (r != 0) && ((r < 0) != (b < 0)) { r + b } else { r }
+}
@@ -0,0 +1,724 @@
+//! Text drawn with pixels, using bitmap fonts
+//!
+//! Unlike the text written by `block::Canvas::text` and `braille::Canvas::text`, which puts one
+//! character in each terminal cell, text drawn with a `Font` is made up of pixels like any other
+//! drawing, so it can be drawn onto any `Surface` and made as large as needed.
+//!
+//! Two fonts covering printable ASCII are built in: `Font::small`, with characters three pixels
+//! wide and five high, and `Font::standard`, with characters five pixels wide and seven high. Fonts
+//! in the BDF and PSF (version 1 or 2) formats can also be loaded.
+//!
+//! # Example
+//!
+//! ```
+//! use drawille::braille::Canvas;
+//! use drawille::font::Font;
+//!
+//! let mut canvas = Canvas::new(60, 16);
+//! Font::standard().draw(&mut canvas, 0, 0, "12:30", 2, true);
+//! println!("{}", canvas.frame());
+//! ```
+
+use std::char;
+use std::collections::HashMap;
+use std::io::File;
+use std::str;
+
+use surface::Surface;
+use super::{Error, InvalidFont, Io};
+
+static PSF1_MAGIC: &'static [u8] = &[0x36, 0x04];
+static PSF2_MAGIC: &'static [u8] = &[0x72, 0xB5, 0x4A, 0x86];
+
+/// The largest width or height of a glyph that will be loaded, in pixels.
+static MAX_GLYPH_SIZE: uint = 1024;
+/// The most glyphs a PSF font may have, which is enough for every UCS-2 code point.
+static MAX_GLYPHS: uint = 65536;
+
+/// A single character of a `Font`.
+#[deriving(Show, Clone, PartialEq, Eq)]
+struct Glyph {
+    /// How far to move along before drawing the next character.
+    advance: i32,
+    /// The position of the bitmap relative to the origin of the character, which is at the left
+    /// of the character and the top of the line.
+    left: i32,
+    top: i32,
+    width: uint,
+    height: uint,
+    bits: Vec<bool>,
+}
+
+impl Glyph {
+    /// Creates a glyph from one of the built-in tables, in which each row of the glyph is a byte
+    /// whose `width` lowest bits are its pixels, leftmost first.
+    fn from_rows(width: uint, rows: &[u8]) -> Glyph {
+        let mut bits = Vec::with_capacity(width * rows.len());
+        for &row in rows.iter() {
+            for x in range(0, width) {
+                bits.push((row >> (width - 1 - x)) & 1 != 0);
+            }
+        }
+        Glyph {
+            advance: width as i32 + 1,
+            left: 0,
+            top: 0,
+            width: width,
+            height: rows.len(),
+            bits: bits,
+        }
+    }
+}
+
+/// A bitmap font.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    height: uint,
+    default: Option<char>,
+}
+
+impl Font {
+    fn empty(height: uint) -> Font {
+        Font { glyphs: HashMap::new(), height: height, default: Some('?') }
+    }
+
+    /// Returns the built-in font with characters three pixels wide and five high.
+    ///
+    /// Lines are six pixels apart and characters four pixels apart, leaving a pixel of space
+    /// between them.
+    pub fn small() -> Font {
+        let mut font = Font::empty(6);
+        for (i, rows) in SMALL.iter().enumerate() {
+            font.glyphs.insert((i as u8 + 32) as char, Glyph::from_rows(3, rows));
+        }
+        font
+    }
+
+    /// Returns the built-in font with characters five pixels wide and seven high.
+    ///
+    /// Lines are eight pixels apart and characters six pixels apart, leaving a pixel of space
+    /// between them.
+    pub fn standard() -> Font {
+        let mut font = Font::empty(8);
+        for (i, rows) in STANDARD.iter().enumerate() {
+            font.glyphs.insert((i as u8 + 32) as char, Glyph::from_rows(5, rows));
+        }
+        font
+    }
+
+    /// Reads a font from a file in either the BDF or PSF format.
+    pub fn open(path: &Path) -> Result<Font, Error> {
+        let data = match File::open(path).and_then(|mut file| file.read_to_end()) {
+            Ok(data) => data,
+            Err(e) => return Err(Io(e)),
+        };
+        if data.as_slice().starts_with(PSF1_MAGIC) || data.as_slice().starts_with(PSF2_MAGIC) {
+            return Font::parse_psf(data.as_slice());
+        }
+        match str::from_utf8(data.as_slice()) {
+            Some(text) => Font::parse_bdf(text),
+            None => Err(invalid("not a BDF or PSF font")),
+        }
+    }
+
+    /// Parses a font in the Glyph Bitmap Distribution Format (BDF).
+    ///
+    /// Glyphs are looked up by their encoding, which is assumed to be Unicode; this is also the
+    /// case for fonts encoded in ISO 8859-1.
+    pub fn parse_bdf(data: &str) -> Result<Font, Error> {
+        let mut font = Font::empty(0);
+        let mut bbox = (0, 0, 0, 0);
+        let (mut ascent, mut descent) = (None, None);
+        let mut lines = data.lines();
+        loop {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => break,
+            };
+            let mut words = line.words();
+            match words.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let n = try!(bdf_numbers(words, 4));
+                    bbox = (n[0], n[1], n[2], n[3]);
+                }
+                Some("FONT_ASCENT") => ascent = Some(try!(bdf_numbers(words, 1))[0]),
+                Some("FONT_DESCENT") => descent = Some(try!(bdf_numbers(words, 1))[0]),
+                Some("DEFAULT_CHAR") => {
+                    font.default = char::from_u32(try!(bdf_numbers(words, 1))[0] as u32);
+                }
+                Some("STARTCHAR") => {
+                    let (_, h, _, yoff) = bbox;
+                    let ascent = match ascent.or(h.checked_add(&yoff)) {
+                        Some(ascent) => ascent,
+                        None => return Err(invalid("font bounding box out of range")),
+                    };
+                    let (encoding, glyph) = try!(bdf_glyph(&mut lines, ascent, bbox));
+                    match encoding {
+                        Some(c) => { font.glyphs.insert(c, glyph); }
+                        None => {},
+                    }
+                }
+                _ => {},
+            }
+        }
+        let (_, h, _, yoff) = bbox;
+        let ascent = ascent.or(h.checked_add(&yoff));
+        let descent = descent.or(0i32.checked_sub(&yoff));
+        let height = ascent.and_then(|a| descent.and_then(|d| a.checked_add(&d)));
+        match height {
+            Some(height) if height > 0 && height as uint <= MAX_GLYPH_SIZE => {
+                font.height = height as uint;
+            }
+            _ => return Err(invalid("missing or invalid font bounding box")),
+        }
+        Ok(font)
+    }
+
+    /// Parses a font in the PC Screen Font format (PSF), version 1 or 2, as used for the Linux
+    /// console.
+    ///
+    /// If the font has no Unicode table, its glyphs are taken to be for the first 256 (or 512)
+    /// code points.
+    pub fn parse_psf(data: &[u8]) -> Result<Font, Error> {
+        let psf1 = data.starts_with(PSF1_MAGIC);
+        let (width, height, count, size, offset, has_table) = if psf1 {
+            if data.len() < 4 {
+                return Err(invalid("truncated font header"));
+            }
+            let mode = data[2];
+            let count = if mode & 0x01 != 0 { 512 } else { 256 };
+            (8, data[3] as uint, count, data[3] as uint, 4, mode & 0x06 != 0)
+        } else if data.starts_with(PSF2_MAGIC) {
+            if data.len() < 32 {
+                return Err(invalid("truncated font header"));
+            }
+            (le32(data, 28), le32(data, 24), le32(data, 16), le32(data, 20), le32(data, 8),
+             le32(data, 12) & 0x01 != 0)
+        } else {
+            return Err(invalid("not a PSF font"));
+        };
+
+        if width > MAX_GLYPH_SIZE || height > MAX_GLYPH_SIZE || count > MAX_GLYPHS {
+            return Err(invalid("glyphs too large or too many"));
+        }
+        let stride = (width + 7) / 8;
+        let end = match count.checked_mul(&size).and_then(|n| n.checked_add(&offset)) {
+            Some(end) if stride * height <= size && end <= data.len() => end,
+            _ => return Err(invalid("truncated glyph data")),
+        };
+        let glyphs: Vec<Glyph> = range(0, count).map(|i| {
+            let bitmap = data.slice(offset + i * size, offset + (i + 1) * size);
+            let mut bits = Vec::with_capacity(width * height);
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    bits.push(bitmap[y * stride + x / 8] & (0x80 >> (x % 8)) != 0);
+                }
+            }
+            Glyph {
+                advance: width as i32,
+                left: 0,
+                top: 0,
+                width: width,
+                height: height,
+                bits: bits,
+            }
+        }).collect();
+
+        let mut font = Font::empty(height);
+        if !has_table {
+            // Glyphs at surrogate code points have no character, and are left out.
+            for (i, glyph) in glyphs.iter().enumerate() {
+                match char::from_u32(i as u32) {
+                    Some(c) => { font.glyphs.insert(c, glyph.clone()); }
+                    None => {},
+                }
+            }
+        } else if psf1 {
+            // Each glyph's entry is a list of little-endian UCS-2 code points, followed by any
+            // sequences of combining characters (which aren't used here) after 0xFFFE, and ended
+            // by 0xFFFF.
+            let mut pos = end;
+            for glyph in glyphs.iter() {
+                let mut in_sequence = false;
+                loop {
+                    if pos + 2 > data.len() {
+                        return Err(invalid("truncated Unicode table"));
+                    }
+                    let v = data[pos] as u32 | (data[pos + 1] as u32 << 8);
+                    pos += 2;
+                    match v {
+                        0xFFFF => break,
+                        0xFFFE => in_sequence = true,
+                        _ if !in_sequence => match char::from_u32(v) {
+                            Some(c) => { font.glyphs.insert(c, glyph.clone()); }
+                            None => {},
+                        },
+                        _ => {},
+                    }
+                }
+            }
+        } else {
+            // Each glyph's entry is UTF-8, with sequences after 0xFE and ended by 0xFF.
+            let mut pos = end;
+            for glyph in glyphs.iter() {
+                let start = pos;
+                while pos < data.len() && data[pos] != 0xFF {
+                    pos += 1;
+                }
+                if pos == data.len() {
+                    return Err(invalid("truncated Unicode table"));
+                }
+                let entry = data.slice(start, pos);
+                let singles = match entry.iter().position(|&b| b == 0xFE) {
+                    Some(i) => entry.slice_to(i),
+                    None => entry,
+                };
+                for c in str::from_utf8(singles).unwrap_or("").chars() {
+                    font.glyphs.insert(c, glyph.clone());
+                }
+                pos += 1;
+            }
+        }
+        Ok(font)
+    }
+
+    /// Returns the distance between the tops of consecutive lines, in unscaled pixels.
+    pub fn line_height(&self) -> uint {
+        self.height
+    }
+
+    /// Sets the character drawn in place of any character the font has no glyph for.
+    ///
+    /// This defaults to `'?'`, or to the font's own default character for BDF fonts. If it is
+    /// `None`, or the font has no glyph for it either, unknown characters are skipped.
+    pub fn set_default(&mut self, c: Option<char>) {
+        self.default = c;
+    }
+
+    /// Returns the width and height, in pixels, of the given text when drawn at the given scale.
+    pub fn measure(&self, text: &str, scale: uint) -> (uint, uint) {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let w = line.chars().filter_map(|c| self.glyph(c)).fold(0, |w, g| w + g.advance);
+            if w as uint > width {
+                width = w as uint;
+            }
+            lines += 1;
+        }
+        (width * scale, lines * self.height * scale)
+    }
+
+    /// Draws text onto a surface with its top-left corner at `(x, y)`, setting each of its pixels
+    /// to `p`.
+    ///
+    /// Every pixel of the font is drawn as a square of `scale` by `scale` pixels. Newlines start a
+    /// new line of text below the first, and pixels not covered by the text are left untouched.
+    pub fn draw<P: Clone, S: Surface<P>>(&self, surface: &mut S, x: i32, y: i32, text: &str,
+                                         scale: uint, p: P) {
+        let scale = scale as i32;
+        for (i, line) in text.split('\n').enumerate() {
+            let top = y + (i * self.height) as i32 * scale;
+            let mut left = x;
+            for c in line.chars() {
+                match self.glyph(c) {
+                    Some(glyph) => {
+                        draw_glyph(surface, glyph, left, top, scale, &p);
+                        left += glyph.advance * scale;
+                    }
+                    None => {},
+                }
+            }
+        }
+    }
+
+    fn glyph<'a>(&'a self, c: char) -> Option<&'a Glyph> {
+        self.glyphs.find(&c).or_else(|| self.default.and_then(|d| self.glyphs.find(&d)))
+    }
+}
+
+fn draw_glyph<P: Clone, S: Surface<P>>(surface: &mut S, glyph: &Glyph, x: i32, y: i32,
+                                        scale: i32, p: &P) {
+    for gy in range(0, glyph.height) {
+        for gx in range(0, glyph.width) {
+            if !glyph.bits[gy * glyph.width + gx] {
+                continue;
+            }
+            let px = x + (glyph.left + gx as i32) * scale;
+            let py = y + (glyph.top + gy as i32) * scale;
+            for dy in range(0, scale) {
+                for dx in range(0, scale) {
+                    surface.set_pixel(px + dx, py + dy, p.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Parses a glyph of a BDF font, from the line after `STARTCHAR` to `ENDCHAR`, returning the
+/// character it is for along with the glyph.
+fn bdf_glyph<'a, I: Iterator<&'a str>>(lines: &mut I, ascent: i32, bbox: (i32, i32, i32, i32))
+                                       -> Result<(Option<char>, Glyph), Error> {
+    let mut encoding = None;
+    let (mut w, mut h, mut xoff, mut yoff) = bbox;
+    let mut advance = w;
+    loop {
+        let line = match lines.next() {
+            Some(line) => line,
+            None => return Err(invalid("unterminated glyph")),
+        };
+        let mut words = line.words();
+        match words.next() {
+            // Glyphs not in the font's encoding have an encoding of -1.
+            Some("ENCODING") => encoding = char::from_u32(try!(bdf_numbers(words, 1))[0] as u32),
+            Some("DWIDTH") => advance = try!(bdf_numbers(words, 1))[0],
+            Some("BBX") => {
+                let n = try!(bdf_numbers(words, 4));
+                w = n[0];
+                h = n[1];
+                xoff = n[2];
+                yoff = n[3];
+            }
+            Some("BITMAP") => break,
+            Some("ENDCHAR") => return Err(invalid("glyph has no bitmap")),
+            _ => {},
+        }
+    }
+    if w < 0 || h < 0 {
+        return Err(invalid("negative glyph dimensions"));
+    }
+    if w as uint > MAX_GLYPH_SIZE || h as uint > MAX_GLYPH_SIZE {
+        return Err(invalid("glyph too large"));
+    }
+    let top = match yoff.checked_add(&h).and_then(|bottom| ascent.checked_sub(&bottom)) {
+        Some(top) => top,
+        None => return Err(invalid("glyph offset out of range")),
+    };
+
+    // Each row is given in hexadecimal, with the leftmost pixel in the most significant bit.
+    let mut bits = Vec::with_capacity((w * h) as uint);
+    for _ in range(0, h) {
+        let row = match lines.next() {
+            Some(line) => line.trim().as_bytes(),
+            None => return Err(invalid("truncated glyph bitmap")),
+        };
+        for x in range(0, w as uint) {
+            let digit = row.get(x / 4).and_then(|&b| (b as char).to_digit(16)).unwrap_or(0);
+            bits.push(digit & (8 >> (x % 4)) != 0);
+        }
+    }
+
+    Ok((encoding, Glyph {
+        advance: advance,
+        left: xoff,
+        top: top,
+        width: w as uint,
+        height: h as uint,
+        bits: bits,
+    }))
+}
+
+/// Parses the numbers following a keyword in a BDF font, of which there must be at least `count`.
+fn bdf_numbers<'a, I: Iterator<&'a str>>(words: I, count: uint) -> Result<Vec<i32>, Error> {
+    let mut numbers = vec![];
+    for word in words {
+        match from_str(word) {
+            Some(n) => numbers.push(n),
+            None => return Err(invalid("expected a number")),
+        }
+    }
+    if numbers.len() < count {
+        return Err(invalid("too few numbers"));
+    }
+    Ok(numbers)
+}
+
+/// Reads a little-endian 32-bit integer, as used in PSF 2 headers.
+fn le32(data: &[u8], i: uint) -> uint {
+    range(0, 4).fold(0, |v, j| v | (data[i + j] as uint << (8 * j)))
+}
+
+fn invalid(reason: &str) -> Error {
+    InvalidFont(reason.to_string())
+}
+
+/// The built-in three by five font, indexed from `' '`, with the rows of each glyph from top to
+/// bottom.
+static SMALL: [[u8, ..5], ..95] = [
+    [0b000, 0b000, 0b000, 0b000, 0b000], // ' '
+    [0b010, 0b010, 0b010, 0b000, 0b010], // '!'
+    [0b101, 0b101, 0b000, 0b000, 0b000], // '"'
+    [0b101, 0b111, 0b101, 0b111, 0b101], // '#'
+    [0b011, 0b110, 0b010, 0b011, 0b110], // '$'
+    [0b101, 0b001, 0b010, 0b100, 0b101], // '%'
+    [0b010, 0b101, 0b010, 0b101, 0b011], // '&'
+    [0b010, 0b010, 0b000, 0b000, 0b000], // '\''
+    [0b001, 0b010, 0b010, 0b010, 0b001], // '('
+    [0b100, 0b010, 0b010, 0b010, 0b100], // ')'
+    [0b000, 0b101, 0b010, 0b101, 0b000], // '*'
+    [0b000, 0b010, 0b111, 0b010, 0b000], // '+'
+    [0b000, 0b000, 0b000, 0b010, 0b100], // ','
+    [0b000, 0b000, 0b111, 0b000, 0b000], // '-'
+    [0b000, 0b000, 0b000, 0b000, 0b010], // '.'
+    [0b001, 0b001, 0b010, 0b100, 0b100], // '/'
+    [0b111, 0b101, 0b101, 0b101, 0b111], // '0'
+    [0b010, 0b110, 0b010, 0b010, 0b111], // '1'
+    [0b111, 0b001, 0b111, 0b100, 0b111], // '2'
+    [0b111, 0b001, 0b111, 0b001, 0b111], // '3'
+    [0b101, 0b101, 0b111, 0b001, 0b001], // '4'
+    [0b111, 0b100, 0b111, 0b001, 0b111], // '5'
+    [0b111, 0b100, 0b111, 0b101, 0b111], // '6'
+    [0b111, 0b001, 0b001, 0b001, 0b001], // '7'
+    [0b111, 0b101, 0b111, 0b101, 0b111], // '8'
+    [0b111, 0b101, 0b111, 0b001, 0b111], // '9'
+    [0b000, 0b010, 0b000, 0b010, 0b000], // ':'
+    [0b000, 0b010, 0b000, 0b010, 0b100], // ';'
+    [0b001, 0b010, 0b100, 0b010, 0b001], // '<'
+    [0b000, 0b111, 0b000, 0b111, 0b000], // '='
+    [0b100, 0b010, 0b001, 0b010, 0b100], // '>'
+    [0b111, 0b001, 0b011, 0b000, 0b010], // '?'
+    [0b010, 0b101, 0b111, 0b100, 0b011], // '@'
+    [0b010, 0b101, 0b111, 0b101, 0b101], // 'A'
+    [0b110, 0b101, 0b110, 0b101, 0b110], // 'B'
+    [0b011, 0b100, 0b100, 0b100, 0b011], // 'C'
+    [0b110, 0b101, 0b101, 0b101, 0b110], // 'D'
+    [0b111, 0b100, 0b110, 0b100, 0b111], // 'E'
+    [0b111, 0b100, 0b110, 0b100, 0b100], // 'F'
+    [0b011, 0b100, 0b101, 0b101, 0b011], // 'G'
+    [0b101, 0b101, 0b111, 0b101, 0b101], // 'H'
+    [0b111, 0b010, 0b010, 0b010, 0b111], // 'I'
+    [0b001, 0b001, 0b001, 0b101, 0b010], // 'J'
+    [0b101, 0b101, 0b110, 0b101, 0b101], // 'K'
+    [0b100, 0b100, 0b100, 0b100, 0b111], // 'L'
+    [0b101, 0b111, 0b111, 0b101, 0b101], // 'M'
+    [0b110, 0b101, 0b101, 0b101, 0b101], // 'N'
+    [0b010, 0b101, 0b101, 0b101, 0b010], // 'O'
+    [0b110, 0b101, 0b110, 0b100, 0b100], // 'P'
+    [0b010, 0b101, 0b101, 0b110, 0b011], // 'Q'
+    [0b110, 0b101, 0b110, 0b101, 0b101], // 'R'
+    [0b011, 0b100, 0b010, 0b001, 0b110], // 'S'
+    [0b111, 0b010, 0b010, 0b010, 0b010], // 'T'
+    [0b101, 0b101, 0b101, 0b101, 0b111], // 'U'
+    [0b101, 0b101, 0b101, 0b101, 0b010], // 'V'
+    [0b101, 0b101, 0b111, 0b111, 0b101], // 'W'
+    [0b101, 0b101, 0b010, 0b101, 0b101], // 'X'
+    [0b101, 0b101, 0b010, 0b010, 0b010], // 'Y'
+    [0b111, 0b001, 0b010, 0b100, 0b111], // 'Z'
+    [0b011, 0b010, 0b010, 0b010, 0b011], // '['
+    [0b100, 0b100, 0b010, 0b001, 0b001], // '\\'
+    [0b110, 0b010, 0b010, 0b010, 0b110], // ']'
+    [0b010, 0b101, 0b000, 0b000, 0b000], // '^'
+    [0b000, 0b000, 0b000, 0b000, 0b111], // '_'
+    [0b100, 0b010, 0b000, 0b000, 0b000], // '`'
+    [0b000, 0b011, 0b101, 0b101, 0b011], // 'a'
+    [0b100, 0b110, 0b101, 0b101, 0b110], // 'b'
+    [0b000, 0b011, 0b100, 0b100, 0b011], // 'c'
+    [0b001, 0b011, 0b101, 0b101, 0b011], // 'd'
+    [0b000, 0b010, 0b111, 0b100, 0b011], // 'e'
+    [0b001, 0b010, 0b111, 0b010, 0b010], // 'f'
+    [0b000, 0b011, 0b101, 0b011, 0b110], // 'g'
+    [0b100, 0b110, 0b101, 0b101, 0b101], // 'h'
+    [0b010, 0b000, 0b010, 0b010, 0b010], // 'i'
+    [0b001, 0b000, 0b001, 0b101, 0b010], // 'j'
+    [0b100, 0b101, 0b110, 0b110, 0b101], // 'k'
+    [0b110, 0b010, 0b010, 0b010, 0b111], // 'l'
+    [0b000, 0b111, 0b111, 0b101, 0b101], // 'm'
+    [0b000, 0b110, 0b101, 0b101, 0b101], // 'n'
+    [0b000, 0b010, 0b101, 0b101, 0b010], // 'o'
+    [0b000, 0b110, 0b101, 0b110, 0b100], // 'p'
+    [0b000, 0b011, 0b101, 0b011, 0b001], // 'q'
+    [0b000, 0b011, 0b100, 0b100, 0b100], // 'r'
+    [0b000, 0b011, 0b110, 0b011, 0b110], // 's'
+    [0b010, 0b111, 0b010, 0b010, 0b011], // 't'
+    [0b000, 0b101, 0b101, 0b101, 0b011], // 'u'
+    [0b000, 0b101, 0b101, 0b101, 0b010], // 'v'
+    [0b000, 0b101, 0b101, 0b111, 0b111], // 'w'
+    [0b000, 0b101, 0b010, 0b010, 0b101], // 'x'
+    [0b000, 0b101, 0b011, 0b001, 0b110], // 'y'
+    [0b000, 0b111, 0b011, 0b110, 0b111], // 'z'
+    [0b011, 0b010, 0b110, 0b010, 0b011], // '{'
+    [0b010, 0b010, 0b010, 0b010, 0b010], // '|'
+    [0b110, 0b010, 0b011, 0b010, 0b110], // '}'
+    [0b000, 0b001, 0b111, 0b100, 0b000], // '~'
+];
+
+/// The built-in five by seven font, laid out like `SMALL`.
+static STANDARD: [[u8, ..7], ..95] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ' '
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100], // '!'
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000], // '"'
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010], // '#'
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100], // '$'
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011], // '%'
+    [0b01000, 0b10100, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101], // '&'
+    [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000], // '\''
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010], // '('
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000], // ')'
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000], // '*'
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000], // '+'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ','
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // '-'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100], // '.'
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000], // '/'
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110], // '0'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // '1'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111], // '2'
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110], // '3'
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010], // '4'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110], // '5'
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110], // '6'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000], // '7'
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110], // '8'
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100], // '9'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000], // ':'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000], // ';'
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010], // '<'
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000], // '='
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000], // '>'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100], // '?'
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110], // '@'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001], // 'A'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110], // 'B'
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110], // 'C'
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100], // 'D'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111], // 'E'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000], // 'F'
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // 'G'
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // 'H'
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'I'
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // 'J'
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001], // 'K'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111], // 'L'
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001], // 'M'
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001], // 'N'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'O'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000], // 'P'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101], // 'Q'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001], // 'R'
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110], // 'S'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // 'T'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'U'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'V'
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010], // 'W'
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001], // 'X'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // 'Y'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111], // 'Z'
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110], // '['
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000], // '\\'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110], // ']'
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000], // '^'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111], // '_'
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000], // '`'
+    [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111], // 'a'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110], // 'b'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110], // 'c'
+    [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111], // 'd'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110], // 'e'
+    [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000], // 'f'
+    [0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'g'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'h'
+    [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110], // 'i'
+    [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100], // 'j'
+    [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010], // 'k'
+    [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'l'
+    [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001], // 'm'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'n'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110], // 'o'
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000], // 'p'
+    [0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001], // 'q'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000], // 'r'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110], // 's'
+    [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110], // 't'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101], // 'u'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'v'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010], // 'w'
+    [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001], // 'x'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'y'
+    [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111], // 'z'
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010], // '{'
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // '|'
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000], // '}'
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000], // '~'
+];
+
+#[cfg(test)]
+mod test {
+    use super::Font;
+
+    /// Builds a PSF 1 font four pixels high, in which glyph `i` has row `r` set to `i + r`.
+    fn psf1() -> Vec<u8> {
+        let mut data = vec![0x36, 0x04, 0x00, 4];
+        for i in range(0u, 256) {
+            for r in range(0u, 4) {
+                data.push((i + r) as u8);
+            }
+        }
+        data
+    }
+
+    /// Builds a PSF 2 header with the given glyph count, glyph size in bytes, height and width.
+    fn psf2(count: u32, size: u32, height: u32, width: u32) -> Vec<u8> {
+        let mut data = vec![0x72, 0xB5, 0x4A, 0x86];
+        for &v in [0, 32, 0, count, size, height, width].iter() {
+            for j in range(0u, 4) {
+                data.push((v >> (8 * j)) as u8);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn parses_psf1() {
+        let font = Font::parse_psf(psf1().as_slice()).unwrap();
+        assert_eq!(font.line_height(), 4);
+        assert_eq!(font.measure("AB\nC", 2), (32, 16));
+        let glyph = font.glyphs.find(&'A').unwrap();
+        assert_eq!((glyph.width, glyph.height, glyph.advance), (8, 4, 8));
+        // The first row of 'A' is 0x41.
+        let row: Vec<bool> = range(0u, 8).map(|x| 0x41u8 & (0x80 >> x) != 0).collect();
+        assert_eq!(glyph.bits.slice_to(8), row.as_slice());
+    }
+
+    #[test]
+    fn rejects_truncated_psf1() {
+        let data = psf1();
+        assert!(Font::parse_psf(data.slice_to(100)).is_err());
+        assert!(Font::parse_psf(data.slice_to(3)).is_err());
+        assert!(Font::parse_psf(b"not a font").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_psf2_headers() {
+        // Sizes whose product overflows, or which claim far more data than is present.
+        assert!(Font::parse_psf(psf2(0xFFFFFFFF, 0xFFFFFFFF, 8, 8).as_slice()).is_err());
+        assert!(Font::parse_psf(psf2(256, 0x10000000, 8, 8).as_slice()).is_err());
+        // Glyphs larger than their own size in bytes.
+        assert!(Font::parse_psf(psf2(1, 1, 8, 8).as_slice()).is_err());
+        assert!(Font::parse_psf(psf2(1, 0xFFFFFFFF, 0xFFFFFFFF, 0xFFFFFFFF).as_slice()).is_err());
+    }
+
+    #[test]
+    fn parses_bdf() {
+        let bdf = "FONTBOUNDINGBOX 4 3 0 -1\nSTARTCHAR A\nENCODING 65\nDWIDTH 5 0\n\
+                   BBX 4 3 0 -1\nBITMAP\n90\n60\nF0\nENDCHAR\n";
+        let font = Font::parse_bdf(bdf).unwrap();
+        assert_eq!(font.line_height(), 3);
+        assert_eq!(font.measure("AA", 1), (10, 3));
+        let glyph = font.glyphs.find(&'A').unwrap();
+        assert_eq!(glyph.bits, vec![true, false, false, true,
+                                    false, true, true, false,
+                                    true, true, true, true]);
+    }
+
+    #[test]
+    fn rejects_bad_bdf_glyphs() {
+        let huge = "FONTBOUNDINGBOX 4 3 0 -1\nSTARTCHAR A\nENCODING 65\n\
+                    BBX 100000 100000 0 0\nBITMAP\nENDCHAR\n";
+        assert!(Font::parse_bdf(huge).is_err());
+        let offset = "FONTBOUNDINGBOX 4 3 0 -1\nSTARTCHAR A\nENCODING 65\n\
+                      BBX 4 3 0 2147483647\nBITMAP\n00\n00\n00\nENDCHAR\n";
+        assert!(Font::parse_bdf(offset).is_err());
+        assert!(Font::parse_bdf("STARTCHAR A\nENCODING 65\nBBX 1 1 0 0\n").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Conversion of images into pictures on a canvas
+//!
+//! Greyscale images are drawn onto a `braille::Canvas`, dithered to one dot per pixel, and colour
+//! images onto a `block::Canvas`, with two pixels to each character cell.
+//!
+//! Images can be built from raw pixel data in any format. With the `image` feature enabled,
+//! images in the Netpbm formats (PBM, PGM and PPM, in both their plain and raw variants) can also
+//! be decoded directly.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use drawille::image::{GrayImage, RgbImage, FloydSteinberg};
+//!
+//! let image = GrayImage::open(&Path::new("logo.pgm")).unwrap();
+//! let canvas = image.to_braille(Some(40), FloydSteinberg, false);
+//! println!("{}", canvas.frame());
+//!
+//! let photo = RgbImage::open(&Path::new("photo.ppm")).unwrap();
+//! println!("{}", photo.to_block(Some(80)).frame());
+//! ```
+
+use std::cmp;
+
+use block;
+use block::Rgb;
+use braille;
+#[cfg(feature = "image")]
+use pnm;
+use super::{Error, InvalidDimensions};
+use super::check_dimensions;
+
+/// How the shades of grey in an image are reduced to pixels that are either set or unset.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum Dither {
+    /// Pixels darker than the given brightness are set, and all others are unset.
+    Threshold(u8),
+    /// Ordered dithering using a 4x4 Bayer matrix, which gives a regular cross-hatched pattern.
+    Bayer,
+    /// Floyd–Steinberg error diffusion, which preserves the most detail.
+    FloydSteinberg,
+    /// Atkinson error diffusion, which gives higher contrast than Floyd–Steinberg by only
+    /// diffusing three quarters of the error.
+    Atkinson,
+}
+
+static BAYER: [[u8, ..4], ..4] = [[0, 8, 2, 10],
+                                  [12, 4, 14, 6],
+                                  [3, 11, 1, 9],
+                                  [15, 7, 13, 5]];
+
+/// A greyscale image, where each pixel is a brightness from 0 (black) to 255 (white).
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct GrayImage {
+    width: uint,
+    height: uint,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// Creates an image from its pixels, given row by row from the top-left corner.
+    ///
+    /// Fails with `InvalidDimensions` if there are not exactly `width * height` pixels, or if the
+    /// image is too large to be drawn onto a canvas.
+    pub fn new(width: uint, height: uint, pixels: Vec<u8>) -> Result<GrayImage, Error> {
+        try!(check_dimensions(width, height));
+        match width.checked_mul(&height) {
+            Some(len) if len == pixels.len() => {},
+            _ => return Err(InvalidDimensions(width, height)),
+        }
+        Ok(GrayImage { width: width, height: height, pixels: pixels })
+    }
+
+    /// Decodes an image in any of the Netpbm formats. Colour images are converted to greyscale.
+    #[cfg(feature = "image")]
+    pub fn decode_pnm(data: &[u8]) -> Result<GrayImage, Error> {
+        let pnm = try!(pnm::decode(data));
+        if pnm.channels == 1 {
+            GrayImage::new(pnm.width, pnm.height, pnm.samples)
+        } else {
+            RgbImage::new(pnm.width, pnm.height, pnm.samples).map(|image| image.to_gray())
+        }
+    }
+
+    /// Reads and decodes a Netpbm image from a file.
+    #[cfg(feature = "image")]
+    pub fn open(path: &Path) -> Result<GrayImage, Error> {
+        GrayImage::decode_pnm(try!(pnm::read(path)).as_slice())
+    }
+
+    pub fn width(&self) -> uint {
+        self.width
+    }
+
+    pub fn height(&self) -> uint {
+        self.height
+    }
+
+    /// Returns the brightness of the pixel at the given coordinates.
+    pub fn get(&self, x: uint, y: uint) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Returns a copy of the image scaled to the given dimensions, where each new pixel is the
+    /// average of the area of the image it covers.
+    pub fn resize(&self, width: uint, height: uint) -> GrayImage {
+        GrayImage {
+            width: width,
+            height: height,
+            pixels: resample(self.pixels.as_slice(), self.width, self.height, 1, width, height),
+        }
+    }
+
+    /// Draws the image onto a new Braille canvas, with one pixel of the image to each dot.
+    ///
+    /// If `cells` is given, the image is first scaled to that many characters wide, keeping its
+    /// aspect ratio (Braille dots are close enough to square that this needs no correction).
+    ///
+    /// Dots are set for the dark areas of the image, as if printing on white paper; if `invert`
+    /// is true they are set for the light areas instead, which suits terminals with dark
+    /// backgrounds.
+    pub fn to_braille(&self, cells: Option<uint>, dither: Dither, invert: bool)
+                      -> braille::Canvas {
+        match cells {
+            Some(cells) if self.width != 0 => {
+                let width = cells * 2;
+                let height = (self.height * width + self.width / 2) / self.width;
+                self.resize(width, height).to_braille(None, dither, invert)
+            }
+            _ => {
+                let mut canvas = braille::Canvas::new(self.width, self.height);
+                for (i, &on) in self.dither(dither).iter().enumerate() {
+                    if on != invert {
+                        canvas.set((i % self.width) as i32, (i / self.width) as i32);
+                    }
+                }
+                canvas
+            }
+        }
+    }
+
+    /// Reduces the image to black and white, returning whether each pixel is black.
+    fn dither(&self, dither: Dither) -> Vec<bool> {
+        let (w, h) = (self.width, self.height);
+        match dither {
+            Threshold(t) => self.pixels.iter().map(|&v| v < t).collect(),
+            Bayer => range(0, w * h).map(|i| {
+                let level = BAYER[(i / w) % 4][(i % w) % 4] as uint;
+                (self.pixels[i] as uint * 16) < level * 255 + 128
+            }).collect(),
+            FloydSteinberg => diffuse(self, &[(1, 0, 7.0 / 16.0), (-1, 1, 3.0 / 16.0),
+                                              (0, 1, 5.0 / 16.0), (1, 1, 1.0 / 16.0)]),
+            Atkinson => diffuse(self, &[(1, 0, 1.0 / 8.0), (2, 0, 1.0 / 8.0),
+                                        (-1, 1, 1.0 / 8.0), (0, 1, 1.0 / 8.0),
+                                        (1, 1, 1.0 / 8.0), (0, 2, 1.0 / 8.0)]),
+        }
+    }
+}
+
+/// A colour image, stored as red, green and blue samples for each pixel in turn.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct RgbImage {
+    width: uint,
+    height: uint,
+    pixels: Vec<u8>,
+}
+
+impl RgbImage {
+    /// Creates an image from its samples, given as red, green and blue for each pixel, row by row
+    /// from the top-left corner.
+    ///
+    /// Fails with `InvalidDimensions` if there are not exactly `width * height * 3` samples, or if
+    /// the image is too large to be drawn onto a canvas.
+    pub fn new(width: uint, height: uint, pixels: Vec<u8>) -> Result<RgbImage, Error> {
+        try!(check_dimensions(width, height));
+        match width.checked_mul(&height).and_then(|n| n.checked_mul(&3)) {
+            Some(len) if len == pixels.len() => {},
+            _ => return Err(InvalidDimensions(width, height)),
+        }
+        Ok(RgbImage { width: width, height: height, pixels: pixels })
+    }
+
+    /// Decodes an image in any of the Netpbm formats. Greyscale images are converted to colour.
+    #[cfg(feature = "image")]
+    pub fn decode_pnm(data: &[u8]) -> Result<RgbImage, Error> {
+        let pnm = try!(pnm::decode(data));
+        if pnm.channels == 3 {
+            return RgbImage::new(pnm.width, pnm.height, pnm.samples);
+        }
+        let mut pixels = Vec::with_capacity(pnm.samples.len() * 3);
+        for &v in pnm.samples.iter() {
+            pixels.push_all(&[v, v, v]);
+        }
+        RgbImage::new(pnm.width, pnm.height, pixels)
+    }
+
+    /// Reads and decodes a Netpbm image from a file.
+    #[cfg(feature = "image")]
+    pub fn open(path: &Path) -> Result<RgbImage, Error> {
+        RgbImage::decode_pnm(try!(pnm::read(path)).as_slice())
+    }
+
+    pub fn width(&self) -> uint {
+        self.width
+    }
+
+    pub fn height(&self) -> uint {
+        self.height
+    }
+
+    /// Returns the red, green and blue components of the pixel at the given coordinates.
+    pub fn get(&self, x: uint, y: uint) -> (u8, u8, u8) {
+        let i = (y * self.width + x) * 3;
+        (self.pixels[i], self.pixels[i + 1], self.pixels[i + 2])
+    }
+
+    /// Returns a copy of the image scaled to the given dimensions, where each new pixel is the
+    /// average of the area of the image it covers.
+    pub fn resize(&self, width: uint, height: uint) -> RgbImage {
+        RgbImage {
+            width: width,
+            height: height,
+            pixels: resample(self.pixels.as_slice(), self.width, self.height, 3, width, height),
+        }
+    }
+
+    /// Converts the image to greyscale.
+    pub fn to_gray(&self) -> GrayImage {
+        GrayImage {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.as_slice().chunks(3).map(|p| luma(p[0], p[1], p[2])).collect(),
+        }
+    }
+
+    /// Draws the image onto a new block canvas, with one pixel of the image to each half of a
+    /// character cell.
+    ///
+    /// If `cells` is given, the image is first scaled to that many characters wide, keeping its
+    /// aspect ratio. Terminal cells are about twice as tall as they are wide, so each half of a
+    /// cell is close to square.
+    pub fn to_block(&self, cells: Option<uint>) -> block::Canvas {
+        match cells {
+            Some(cells) if self.width != 0 => {
+                let height = (self.height * cells + self.width / 2) / self.width;
+                self.resize(cells, height).to_block(None)
+            }
+            _ => {
+                let mut canvas = block::Canvas::new(self.width, self.height);
+                for y in range(0, self.height) {
+                    for x in range(0, self.width) {
+                        let (r, g, b) = self.get(x, y);
+                        canvas.set(x as i32, y as i32, Rgb(r, g, b));
+                    }
+                }
+                canvas
+            }
+        }
+    }
+}
+
+/// Reduces an image to black and white by error diffusion, spreading the error of each pixel to
+/// its neighbours at the given offsets in the given proportions.
+fn diffuse(image: &GrayImage, weights: &[(int, int, f64)]) -> Vec<bool> {
+    let (w, h) = (image.width as int, image.height as int);
+    let mut values: Vec<f64> = image.pixels.iter().map(|&v| v as f64).collect();
+    let mut result = Vec::with_capacity(values.len());
+    for y in range(0, h) {
+        for x in range(0, w) {
+            let old = values[(y * w + x) as uint];
+            let black = old < 128.0;
+            let error = old - if black { 0.0 } else { 255.0 };
+            result.push(black);
+            for &(dx, dy, weight) in weights.iter() {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0 && nx < w && ny < h {
+                    *values.get_mut((ny * w + nx) as uint) += error * weight;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Returns the brightness of a colour, using the Rec. 709 luma coefficients.
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64 + 0.5) as u8
+}
+
+/// Scales an image with `channels` samples per pixel to the given dimensions by averaging the
+/// area of the source image covered by each new pixel.
+fn resample(src: &[u8], width: uint, height: uint, channels: uint, new_width: uint,
+            new_height: uint) -> Vec<u8> {
+    if width == 0 || height == 0 {
+        return Vec::from_elem(new_width * new_height * channels, 0);
+    }
+    let mut result = Vec::with_capacity(new_width * new_height * channels);
+    let sx = width as f64 / new_width as f64;
+    let sy = height as f64 / new_height as f64;
+
+    for y in range(0, new_height) {
+        let (top, bottom) = (y as f64 * sy, (y + 1) as f64 * sy);
+        for x in range(0, new_width) {
+            let (left, right) = (x as f64 * sx, (x + 1) as f64 * sx);
+            let mut totals = Vec::from_elem(channels, 0.0f64);
+            let mut area = 0.0;
+            for py in range(top.floor() as uint, cmp::min(bottom.ceil() as uint, height)) {
+                // The portion of this row of source pixels that the new pixel covers.
+                let dy = bottom.min((py + 1) as f64) - top.max(py as f64);
+                for px in range(left.floor() as uint, cmp::min(right.ceil() as uint, width)) {
+                    let dx = right.min((px + 1) as f64) - left.max(px as f64);
+                    let i = (py * width + px) * channels;
+                    for c in range(0, channels) {
+                        *totals.get_mut(c) += src[i + c] as f64 * dx * dy;
+                    }
+                    area += dx * dy;
+                }
+            }
+            for &total in totals.iter() {
+                result.push((total / area + 0.5) as u8);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,225 @@
+//! A decoder for the Netpbm image formats
+
+use std::cmp;
+use std::io::File;
+
+use super::{Error, InvalidDimensions, InvalidImage, Io};
+use super::check_dimensions;
+
+/// Reads the whole of a file.
+pub fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    match File::open(path).and_then(|mut file| file.read_to_end()) {
+        Ok(data) => Ok(data),
+        Err(e) => Err(Io(e)),
+    }
+}
+
+/// The contents of a Netpbm image, with samples scaled to the range 0 to 255.
+pub struct Pnm {
+    pub width: uint,
+    pub height: uint,
+    pub channels: uint,
+    pub samples: Vec<u8>,
+}
+
+/// Decodes an image in any of the Netpbm formats.
+pub fn decode(data: &[u8]) -> Result<Pnm, Error> {
+    let mut parser = Parser { data: data, pos: 0 };
+    if data.len() < 2 || data[0] != b'P' {
+        return Err(InvalidImage("not a Netpbm image".to_string()));
+    }
+    let format = data[1];
+    parser.pos = 2;
+
+    let (bitmap, channels, plain) = match format {
+        b'1' => (true, 1, true),
+        b'2' => (false, 1, true),
+        b'3' => (false, 3, true),
+        b'4' => (true, 1, false),
+        b'5' => (false, 1, false),
+        b'6' => (false, 3, false),
+        _ => return Err(InvalidImage("unsupported Netpbm format".to_string())),
+    };
+    let width = try!(parser.number());
+    let height = try!(parser.number());
+    let maxval = if bitmap { 1 } else { try!(parser.number()) };
+    if maxval == 0 || maxval > 65535 {
+        return Err(InvalidImage("invalid maximum sample value".to_string()));
+    }
+    try!(check_dimensions(width, height));
+    let count = match width.checked_mul(&height).and_then(|n| n.checked_mul(&channels)) {
+        Some(count) => count,
+        None => return Err(InvalidDimensions(width, height)),
+    };
+    let size = if maxval > 255 { 2 } else { 1 };
+
+    // Check that the data is long enough for every sample before allocating space for them, so
+    // that a short file with a huge header can't exhaust memory. Plain samples take at least a
+    // byte each, and raw ones follow a single whitespace character after the header.
+    let (start, needed) = if plain {
+        (parser.pos, Some(count))
+    } else if bitmap {
+        (parser.pos + 1, Some((width + 7) / 8 * height))
+    } else {
+        (parser.pos + 1, count.checked_mul(&size))
+    };
+    match needed {
+        Some(needed) if start <= data.len() && needed <= data.len() - start => {},
+        _ => return Err(InvalidImage("truncated image data".to_string())),
+    }
+    let mut samples = Vec::with_capacity(count);
+
+    if plain {
+        for _ in range(0, count) {
+            let v = if bitmap { try!(parser.bit()) } else { try!(parser.number()) };
+            samples.push(scale(v, maxval, bitmap));
+        }
+    } else {
+        parser.pos = start;
+        if bitmap {
+            let stride = (width + 7) / 8;
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    let byte = try!(parser.byte_at(parser.pos + y * stride + x / 8));
+                    samples.push(scale(((byte >> (7 - x % 8)) & 1) as uint, 1, true));
+                }
+            }
+        } else {
+            for i in range(0, count) {
+                let offset = parser.pos + i * size;
+                let mut v = 0;
+                for j in range(0, size) {
+                    v = (v << 8) | try!(parser.byte_at(offset + j)) as uint;
+                }
+                samples.push(scale(v, maxval, false));
+            }
+        }
+    }
+
+    Ok(Pnm { width: width, height: height, channels: channels, samples: samples })
+}
+
+/// Scales a sample to the range 0 to 255. In bitmaps, 1 means black.
+fn scale(v: uint, maxval: uint, bitmap: bool) -> u8 {
+    if bitmap {
+        if v == 1 { 0 } else { 255 }
+    } else {
+        ((cmp::min(v, maxval) * 255 + maxval / 2) / maxval) as u8
+    }
+}
+
+/// Reads the header and plain raster of a Netpbm image.
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    /// Skips whitespace and comments.
+    fn skip(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b' ' | b'\t' | b'\r' | b'\n' => self.pos += 1,
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Reads a decimal number.
+    fn number(&mut self) -> Result<uint, Error> {
+        self.skip();
+        let start = self.pos;
+        let mut v = 0u;
+        while self.pos < self.data.len() && self.data[self.pos] >= b'0' &&
+              self.data[self.pos] <= b'9' {
+            v = match v.checked_mul(&10).and_then(|v| {
+                v.checked_add(&((self.data[self.pos] - b'0') as uint))
+            }) {
+                Some(v) => v,
+                None => return Err(InvalidImage("number too large".to_string())),
+            };
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(InvalidImage("expected a number".to_string()));
+        }
+        Ok(v)
+    }
+
+    /// Reads a single pixel of a plain bitmap, which need not be separated by whitespace.
+    fn bit(&mut self) -> Result<uint, Error> {
+        self.skip();
+        match self.data.get(self.pos) {
+            Some(&b'0') => { self.pos += 1; Ok(0) }
+            Some(&b'1') => { self.pos += 1; Ok(1) }
+            _ => Err(InvalidImage("expected a 0 or 1".to_string())),
+        }
+    }
+
+    fn byte_at(&self, pos: uint) -> Result<u8, Error> {
+        match self.data.get(pos) {
+            Some(&b) => Ok(b),
+            None => Err(InvalidImage("truncated image data".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode;
+
+    #[test]
+    fn decodes_plain_bitmap() {
+        let pnm = decode(b"P1\n# a comment\n3 2\n1 0 1\n010\n").unwrap();
+        assert_eq!((pnm.width, pnm.height, pnm.channels), (3, 2, 1));
+        assert_eq!(pnm.samples, vec![0, 255, 0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn decodes_raw_bitmap() {
+        let pnm = decode(b"P4\n3 2\n\xa0\x40").unwrap();
+        assert_eq!((pnm.width, pnm.height, pnm.channels), (3, 2, 1));
+        assert_eq!(pnm.samples, vec![0, 255, 0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn decodes_raw_graymap() {
+        let pnm = decode(b"P5 2 2 255\n\x00\x80\xff\x10").unwrap();
+        assert_eq!((pnm.width, pnm.height, pnm.channels), (2, 2, 1));
+        assert_eq!(pnm.samples, vec![0, 128, 255, 16]);
+
+        let pnm = decode(b"P5 2 1 65535\n\xff\xff\x00\x00").unwrap();
+        assert_eq!(pnm.samples, vec![255, 0]);
+    }
+
+    #[test]
+    fn decodes_plain_pixmap() {
+        let pnm = decode(b"P3 1 1 15\n15 0 7\n").unwrap();
+        assert_eq!((pnm.width, pnm.height, pnm.channels), (1, 1, 3));
+        assert_eq!(pnm.samples, vec![255, 0, 119]);
+    }
+
+    #[test]
+    fn rejects_invalid_images() {
+        assert!(decode(b"").is_err());
+        assert!(decode(b"GIF89a").is_err());
+        assert!(decode(b"P7 1 1 255\n\x00").is_err());
+        assert!(decode(b"P5 2 2\n").is_err());
+        assert!(decode(b"P5 2 2 0\n\x00\x00\x00\x00").is_err());
+        assert!(decode(b"P5 2 2 255\n\x00").is_err());
+        assert!(decode(b"P1 2 2\n1 0 1").is_err());
+        assert!(decode(b"P4 9 1\n\xff").is_err());
+    }
+
+    #[test]
+    fn rejects_huge_headers() {
+        assert!(decode(b"P5 4294967296 4294967296 255\n").is_err());
+        assert!(decode(b"P6 2000000000 2000000000 255\n\x00").is_err());
+        assert!(decode(b"P1 100000 100000\n1").is_err());
+    }
+}
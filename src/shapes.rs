@@ -0,0 +1,370 @@
+//! Rasterisation of geometric shapes
+//!
+//! `line_vec`, `line_points` and `regular_polygon_points` are independent of any particular canvas
+//! and produce coordinates only. The remaining functions draw onto any `Surface`, so they work
+//! with both the Braille and the block canvases.
+//!
+//! # Example
+//!
+//! ```
+//! use drawille::braille::Canvas;
+//! use drawille::shapes;
+//!
+//! let mut canvas = Canvas::new(40, 40);
+//! shapes::circle(&mut canvas, 20, 20, 15, true);
+//! shapes::fill_rect(&mut canvas, 15, 15, 10, 10, true);
+//! println!("{}", canvas.frame());
+//! ```
+
+use surface::Surface;
+use super::{round_coord, round_point};
+
+/// Returns the pixels on the line between `(x1, y1)` and `(x2, y2)`, inclusive of both ends.
+///
+/// This uses Bresenham's line algorithm, so every octant is handled with integer arithmetic only.
+/// The same set of pixels is produced regardless of which end the line is drawn from; the
+/// returned points always run from `(x1, y1)` to `(x2, y2)`.
+///
+/// Every pixel of the line is held in memory at once; to draw very long lines, iterate over
+/// `line_points` instead.
+pub fn line_vec(x1: i32, y1: i32, x2: i32, y2: i32) -> Vec<(i32, i32)> {
+    let mut result: Vec<(i32, i32)> = line_points(x1, y1, x2, y2).collect();
+    if (x2, y2) < (x1, y1) {
+        result.reverse();
+    }
+    result
+}
+
+/// Returns an iterator over the same pixels as `line_vec`, which only computes each pixel as it
+/// is reached.
+///
+/// The pixels run from whichever end is smaller, comparing `x` and then `y`, to the other.
+pub fn line_points(x1: i32, y1: i32, x2: i32, y2: i32) -> LinePoints {
+    // Always rasterise in the same direction so that swapping the ends can't change which pixel
+    // is picked when the error term is exactly halfway between two candidates.
+    let (x1, y1, x2, y2) = if (x2, y2) < (x1, y1) { (x2, y2, x1, y1) } else { (x1, y1, x2, y2) };
+    // The differences between two `i32`s, and twice the error term, only fit in an `i64`.
+    let (x1, y1, x2, y2) = (x1 as i64, y1 as i64, x2 as i64, y2 as i64);
+    let dx = (x2 - x1).abs();
+    let dy = -(y2 - y1).abs();
+    LinePoints {
+        x: x1,
+        y: y1,
+        x2: x2,
+        y2: y2,
+        dx: dx,
+        dy: dy,
+        xdir: if x1 <= x2 { 1 } else { -1 },
+        ydir: if y1 <= y2 { 1 } else { -1 },
+        err: dx + dy,
+        done: false,
+    }
+}
+
+/// An iterator over the pixels on a line, as returned by `line_points`.
+pub struct LinePoints {
+    x: i64,
+    y: i64,
+    x2: i64,
+    y2: i64,
+    dx: i64,
+    dy: i64,
+    xdir: i64,
+    ydir: i64,
+    err: i64,
+    done: bool,
+}
+
+impl Iterator<(i32, i32)> for LinePoints {
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.done {
+            return None;
+        }
+        let point = (self.x as i32, self.y as i32);
+        if self.x == self.x2 && self.y == self.y2 {
+            self.done = true;
+            return Some(point);
+        }
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.x += self.xdir;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.y += self.ydir;
+        }
+        Some(point)
+    }
+}
+
+/// The rule used to decide which pixels lie inside a self-intersecting polygon.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    /// A pixel is inside if a ray from it crosses the outline an odd number of times.
+    EvenOdd,
+    /// A pixel is inside if the outline winds around it a non-zero number of times.
+    NonZero,
+}
+
+/// Draws a line from `(x1, y1)` to `(x2, y2)` onto any `Surface`.
+pub fn line<P: Clone, S: Surface<P>>(s: &mut S, x1: i32, y1: i32, x2: i32, y2: i32, p: P) {
+    for (x, y) in line_points(x1, y1, x2, y2) {
+        s.set_pixel(x, y, p.clone());
+    }
+}
+
+/// Draws the outline of the `width` by `height` rectangle whose top-left corner is at `(x, y)`.
+pub fn rect<P: Clone, S: Surface<P>>(s: &mut S, x: i32, y: i32, width: i32, height: i32, p: P) {
+    if width <= 0 || height <= 0 {
+        return;
+    }
+    let (x2, y2) = (x + width - 1, y + height - 1);
+    polygon(s, &[(x, y), (x2, y), (x2, y2), (x, y2)], p);
+}
+
+/// Fills the `width` by `height` rectangle whose top-left corner is at `(x, y)`.
+pub fn fill_rect<P: Clone, S: Surface<P>>(s: &mut S, x: i32, y: i32, width: i32, height: i32,
+                                         p: P) {
+    for py in range(y, y + height) {
+        for px in range(x, x + width) {
+            s.set_pixel(px, py, p.clone());
+        }
+    }
+}
+
+/// Draws the outline of the circle of radius `r` centred on `(cx, cy)`.
+pub fn circle<P: Clone, S: Surface<P>>(s: &mut S, cx: i32, cy: i32, r: i32, p: P) {
+    ellipse(s, cx, cy, r, r, p);
+}
+
+/// Fills the circle of radius `r` centred on `(cx, cy)`.
+pub fn fill_circle<P: Clone, S: Surface<P>>(s: &mut S, cx: i32, cy: i32, r: i32, p: P) {
+    fill_ellipse(s, cx, cy, r, r, p);
+}
+
+/// Draws the outline of the axis-aligned ellipse centred on `(cx, cy)` with horizontal radius
+/// `rx` and vertical radius `ry`, using the midpoint ellipse algorithm.
+pub fn ellipse<P: Clone, S: Surface<P>>(s: &mut S, cx: i32, cy: i32, rx: i32, ry: i32, p: P) {
+    for &(x, y) in ellipse_quadrant(rx, ry).iter() {
+        s.set_pixel(cx + x, cy + y, p.clone());
+        s.set_pixel(cx - x, cy + y, p.clone());
+        s.set_pixel(cx + x, cy - y, p.clone());
+        s.set_pixel(cx - x, cy - y, p.clone());
+    }
+}
+
+/// Fills the axis-aligned ellipse centred on `(cx, cy)` with horizontal radius `rx` and vertical
+/// radius `ry`. The filled area covers exactly the pixels drawn by `ellipse` and everything inside
+/// them.
+pub fn fill_ellipse<P: Clone, S: Surface<P>>(s: &mut S, cx: i32, cy: i32, rx: i32, ry: i32,
+                                            p: P) {
+    for &(x, y) in ellipse_quadrant(rx, ry).iter() {
+        for px in range(cx - x, cx + x + 1) {
+            s.set_pixel(px, cy + y, p.clone());
+            s.set_pixel(px, cy - y, p.clone());
+        }
+    }
+}
+
+/// Draws the arc of the circle of radius `r` centred on `(cx, cy)` running anticlockwise from
+/// `start` to `end` degrees, where 0° points along the positive x axis.
+///
+/// Since the y axis points down the terminal, 90° is directly above the centre.
+pub fn arc<P: Clone, S: Surface<P>>(s: &mut S, cx: i32, cy: i32, r: i32, start: f64, end: f64,
+                                   p: P) {
+    let sweep = end - start;
+    for &(x, y) in ellipse_quadrant(r, r).iter() {
+        for &(dx, dy) in [(x, y), (-x, y), (x, -y), (-x, -y)].iter() {
+            let angle = (-dy as f64).atan2(dx as f64).to_degrees();
+            if sweep >= 360.0 || normalise_degrees(angle - start) <= normalise_degrees(sweep) {
+                s.set_pixel(cx + dx, cy + dy, p.clone());
+            }
+        }
+    }
+}
+
+/// Draws the outline of the polygon with the given vertices; the last vertex is joined back to
+/// the first.
+pub fn polygon<P: Clone, S: Surface<P>>(s: &mut S, points: &[(i32, i32)], p: P) {
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        line(s, x1, y1, x2, y2, p.clone());
+    }
+}
+
+/// Fills the polygon with the given vertices, deciding which areas of a self-intersecting
+/// polygon are inside according to `rule`. The outline is always included in the filled area.
+pub fn fill_polygon<P: Clone, S: Surface<P>>(s: &mut S, points: &[(i32, i32)], rule: FillRule,
+                                            p: P) {
+    if points.is_empty() {
+        return;
+    }
+    let miny = points.iter().map(|&(_, y)| y).min().unwrap();
+    let maxy = points.iter().map(|&(_, y)| y).max().unwrap();
+
+    for y in range(miny, maxy + 1) {
+        // Every edge crossing this scanline, with the direction the edge is travelling in. Edges
+        // include their upper end but not their lower one so that shared vertices count once.
+        let mut crossings = vec![];
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            if (y1 <= y && y < y2) || (y2 <= y && y < y1) {
+                let t = (y - y1) as f64 / (y2 - y1) as f64;
+                let x = x1 as f64 + t * (x2 - x1) as f64;
+                crossings.push((x, if y1 < y2 { 1i } else { -1 }));
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut winding = 0i;
+        for (i, &(x, dir)) in crossings.iter().enumerate() {
+            winding += dir;
+            let inside = match rule {
+                EvenOdd => i % 2 == 0,
+                NonZero => winding != 0,
+            };
+            if inside && i + 1 < crossings.len() {
+                let (next, _) = crossings[i + 1];
+                // Crossings lie between integer vertices, so they are always finite.
+                let (from, to) = (round_coord(x).unwrap(), round_coord(next).unwrap());
+                for px in range(from, to + 1) {
+                    s.set_pixel(px, y, p.clone());
+                }
+            }
+        }
+    }
+
+    polygon(s, points, p);
+}
+
+/// Returns the vertices of the regular polygon with `sides` sides inscribed in the circle of
+/// radius `r` centred on `(cx, cy)`. The first vertex lies `rotation` degrees anticlockwise from
+/// the positive x axis.
+///
+/// If `r` or `rotation` is infinite or NaN, there are no vertices.
+pub fn regular_polygon_points(cx: i32, cy: i32, r: f64, sides: uint, rotation: f64)
+                              -> Vec<(i32, i32)> {
+    range(0, sides).filter_map(|i| {
+        let angle = (rotation + 360.0 * i as f64 / sides as f64).to_radians();
+        round_point(cx as f64 + r * angle.cos(), cy as f64 - r * angle.sin())
+    }).collect()
+}
+
+/// Maps an angle in degrees onto the range `[0, 360)`.
+fn normalise_degrees(angle: f64) -> f64 {
+    let a = angle % 360.0;
+    if a < 0.0 { a + 360.0 } else { a }
+}
+
+/// Returns the pixels of one quadrant of the ellipse with the given radii, as offsets from its
+/// centre with both components non-negative.
+fn ellipse_quadrant(rx: i32, ry: i32) -> Vec<(i32, i32)> {
+    if rx < 0 || ry < 0 {
+        return vec![];
+    }
+    if rx == 0 || ry == 0 {
+        // The ellipse has collapsed into a straight line.
+        return line_vec(0, 0, rx, ry);
+    }
+
+    let (rx2, ry2) = (rx as i64 * rx as i64, ry as i64 * ry as i64);
+    let (mut x, mut y) = (0i64, ry as i64);
+    let (mut px, mut py) = (0i64, 2 * rx2 * y);
+    let mut result = vec![];
+
+    // Region 1: the slope is shallower than -1, so step along x.
+    let mut d = ry2 - rx2 * ry as i64 + rx2 / 4;
+    while px < py {
+        result.push((x as i32, y as i32));
+        x += 1;
+        px += 2 * ry2;
+        if d < 0 {
+            d += ry2 + px;
+        } else {
+            y -= 1;
+            py -= 2 * rx2;
+            d += ry2 + px - py;
+        }
+    }
+
+    // Region 2: the slope is steeper than -1, so step along y.
+    d = (ry2 * (2 * x + 1) * (2 * x + 1)) / 4 + rx2 * (y - 1) * (y - 1) - rx2 * ry2;
+    while y >= 0 {
+        result.push((x as i32, y as i32));
+        y -= 1;
+        py -= 2 * rx2;
+        if d > 0 {
+            d += rx2 - py;
+        } else {
+            x += 1;
+            px += 2 * ry2;
+            d += rx2 - py + px;
+        }
+    }
+
+    // Very flat ellipses reach the x axis before reaching `rx`, so finish along the axis.
+    let last = result.last().map_or(0, |&(x, _)| x);
+    for x in range(last + 1, rx + 1) {
+        result.push((x, 0));
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp;
+
+    use std::i32;
+
+    use super::{ellipse_quadrant, line_points, line_vec};
+
+    #[test]
+    fn line_vec_is_symmetric() {
+        // Every endpoint within a square around the origin covers all eight octants, along with
+        // the horizontal, vertical and diagonal lines between them.
+        for x in range(-12i32, 13) {
+            for y in range(-12i32, 13) {
+                let forward = line_vec(0, 0, x, y);
+                let mut backward = line_vec(x, y, 0, 0);
+                backward.reverse();
+                assert_eq!(forward, backward);
+            }
+        }
+    }
+
+    #[test]
+    fn line_vec_is_connected() {
+        for x in range(-12i32, 13) {
+            for y in range(-12i32, 13) {
+                let points = line_vec(3, -2, x, y);
+                assert_eq!(points[0], (3, -2));
+                assert_eq!(*points.last().unwrap(), (x, y));
+                assert_eq!(points.len() as i32, cmp::max((x - 3).abs(), (y + 2).abs()) + 1);
+                for pair in points.as_slice().windows(2) {
+                    let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                    assert!((x2 - x1).abs() <= 1 && (y2 - y1).abs() <= 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_lines_dont_overflow() {
+        // Twice the error term of this line doesn't fit in an `i32`.
+        let start: Vec<(i32, i32)> = line_points(0, 0, 1_100_000_000, 3).take(3).collect();
+        assert_eq!(start, vec![(0, 0), (1, 0), (2, 0)]);
+        let corner = line_vec(i32::MAX, i32::MIN, i32::MAX - 3, i32::MIN + 3);
+        assert_eq!(corner, vec![(i32::MAX, i32::MIN), (i32::MAX - 1, i32::MIN + 1),
+                                (i32::MAX - 2, i32::MIN + 2), (i32::MAX - 3, i32::MIN + 3)]);
+    }
+
+    #[test]
+    fn flat_ellipses_reach_their_ends() {
+        for &(rx, ry) in [(15, 1), (40, 2), (80, 3), (1, 1), (5, 5)].iter() {
+            let quadrant = ellipse_quadrant(rx, ry);
+            assert_eq!(quadrant[0], (0, ry));
+            assert_eq!(*quadrant.last().unwrap(), (rx, 0));
+        }
+    }
+}
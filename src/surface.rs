@@ -0,0 +1,47 @@
+//! A common interface to the canvases provided by this crate
+//!
+//! Code that only needs to plot pixels and show the result can be written against `Surface` and
+//! then used with whichever canvas best suits the terminal: a `braille::Canvas` stores whether
+//! each pixel is set (`bool`), while a `block::Canvas` stores a colour for each pixel (`Color`).
+
+use std::io::{IoResult, Writer};
+
+/// Something that can be drawn to the terminal as lines of text.
+pub trait Render {
+    /// Returns a `Vec` of each row of the output.
+    fn rows(&self) -> Vec<String>;
+
+    /// Returns the output for each character cell, as a `Vec` of rows.
+    ///
+    /// Each cell's `String` includes any escape sequences needed to display it correctly on its
+    /// own, so that cells can be redrawn individually; see the `diff` module.
+    fn cells(&self) -> Vec<Vec<String>>;
+
+    /// Draws the output to a `String` and returns it.
+    fn frame(&self) -> String {
+        self.rows().connect("\n")
+    }
+
+    /// Draws the output to `out` in the same form as `frame`.
+    ///
+    /// The canvases in this crate write each row as it is rendered rather than building the whole
+    /// frame first.
+    fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        out.write_str(self.frame().as_slice())
+    }
+}
+
+/// A grid of pixels, each holding a value of type `P`, that can be rendered to the terminal.
+pub trait Surface<P>: Render {
+    /// Returns the width and height of the surface in pixels.
+    ///
+    /// Surfaces that expand when drawn outside of their dimensions report the dimensions they
+    /// were created with.
+    fn dimensions(&self) -> (uint, uint);
+
+    /// Sets the pixel at the specified coordinates to `p`.
+    fn set_pixel(&mut self, x: i32, y: i32, p: P);
+
+    /// Returns the value of the pixel at the specified coordinates.
+    fn get_pixel(&self, x: i32, y: i32) -> P;
+}
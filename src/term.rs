@@ -0,0 +1,102 @@
+//! Terminal capabilities
+//!
+//! Terminals vary widely in how many colours they can display. This module detects what the
+//! terminal in use supports from the environment, so that canvases can restrict themselves to
+//! colours that will actually be shown.
+
+use std::os;
+
+/// The range of colours a terminal is able to display.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// No colour at all; output should contain no colour escape sequences.
+    Monochrome,
+    /// The eight base ANSI colours.
+    Colors8,
+    /// The eight base ANSI colours and their bright variants.
+    Colors16,
+    /// The xterm 256-colour palette.
+    Colors256,
+    /// Arbitrary 24-bit colours.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detects the colour depth of the terminal in use from the `NO_COLOR`, `COLORTERM` and
+    /// `TERM` environment variables.
+    pub fn detect() -> ColorDepth {
+        let no_color = os::getenv("NO_COLOR");
+        let colorterm = os::getenv("COLORTERM");
+        let term = os::getenv("TERM");
+        ColorDepth::from_env(term.as_ref().map(|s| s.as_slice()),
+                             colorterm.as_ref().map(|s| s.as_slice()),
+                             no_color.as_ref().map(|s| s.as_slice()))
+    }
+
+    /// Determines a colour depth from the values of the `TERM`, `COLORTERM` and `NO_COLOR`
+    /// environment variables, where `None` means the variable is unset.
+    ///
+    /// A non-empty `NO_COLOR` disables colour entirely, following <http://no-color.org/>. Otherwise
+    /// a `COLORTERM` of `truecolor` or `24bit` indicates 24-bit colour, and failing that the depth
+    /// is guessed from the name of the terminal in `TERM`.
+    pub fn from_env(term: Option<&str>, colorterm: Option<&str>, no_color: Option<&str>)
+                    -> ColorDepth {
+        if no_color.map(|s| !s.is_empty()).unwrap_or(false) {
+            return Monochrome;
+        }
+        match colorterm {
+            Some("truecolor") | Some("24bit") => return TrueColor,
+            _ => {},
+        }
+
+        let term = match term {
+            None | Some("") | Some("dumb") => return Monochrome,
+            Some(term) => term,
+        };
+        if term.ends_with("-direct") {
+            TrueColor
+        } else if term.contains("256color") {
+            Colors256
+        } else if term.contains("16color") || term == "linux" ||
+                  ["xterm", "rxvt", "screen", "tmux"].iter().any(|t| term.starts_with(*t)) {
+            Colors16
+        } else {
+            Colors8
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColorDepth, Monochrome, Colors8, Colors16, Colors256, TrueColor};
+
+    #[test]
+    fn no_color_takes_precedence() {
+        assert_eq!(ColorDepth::from_env(Some("xterm-256color"), Some("truecolor"), Some("1")),
+                   Monochrome);
+        // An empty `NO_COLOR` is treated as unset.
+        assert_eq!(ColorDepth::from_env(Some("xterm-256color"), None, Some("")), Colors256);
+    }
+
+    #[test]
+    fn colorterm_indicates_true_color() {
+        assert_eq!(ColorDepth::from_env(Some("xterm"), Some("truecolor"), None), TrueColor);
+        assert_eq!(ColorDepth::from_env(Some("dumb"), Some("24bit"), None), TrueColor);
+        assert_eq!(ColorDepth::from_env(None, Some("24bit"), None), TrueColor);
+        assert_eq!(ColorDepth::from_env(Some("xterm"), Some("yes"), None), Colors16);
+    }
+
+    #[test]
+    fn term_names() {
+        let depth = |term| ColorDepth::from_env(term, None, None);
+        assert_eq!(depth(Some("xterm-256color")), Colors256);
+        assert_eq!(depth(Some("screen-256color")), Colors256);
+        assert_eq!(depth(Some("xterm-direct")), TrueColor);
+        assert_eq!(depth(Some("xterm")), Colors16);
+        assert_eq!(depth(Some("linux")), Colors16);
+        assert_eq!(depth(Some("vt100")), Colors8);
+        assert_eq!(depth(Some("dumb")), Monochrome);
+        assert_eq!(depth(Some("")), Monochrome);
+        assert_eq!(depth(None), Monochrome);
+    }
+}
@@ -0,0 +1,155 @@
+//! Turtle graphics on top of a Braille canvas
+//!
+//! This is a port of the `Turtle` class from the Python library
+//! [drawille](https://github.com/asciimoo/drawille), extended with a stack of saved states so
+//! that branching drawings such as L-systems can be rendered directly.
+//!
+//! # Example
+//!
+//! ```
+//! use drawille::turtle::Turtle;
+//!
+//! let mut turtle = Turtle::new(0.0, 0.0);
+//! for _ in range(0u, 36) {
+//!     turtle.right(10.0);
+//!     for _ in range(0u, 36) {
+//!         turtle.right(10.0);
+//!         turtle.forward(8.0);
+//!     }
+//! }
+//! println!("{}", turtle.canvas.frame());
+//! ```
+
+use std::io::{IoResult, Writer};
+
+use braille::Canvas;
+use surface::Render;
+
+/// The position, heading and pen state of a `Turtle`.
+#[deriving(Show, Clone, PartialEq)]
+struct State {
+    x: f64,
+    y: f64,
+    heading: f64,
+    pen: bool,
+}
+
+/// A turtle that draws onto a Braille `Canvas` as it moves.
+///
+/// The heading is measured in degrees clockwise from the positive x axis, so a turtle with a
+/// heading of 90° moves down the terminal. Turtles start with their pen down.
+#[deriving(Clone)]
+pub struct Turtle {
+    /// The canvas the turtle draws onto.
+    pub canvas: Canvas,
+    state: State,
+    stack: Vec<State>,
+}
+
+impl Turtle {
+    /// Creates a new `Turtle` at the given position, drawing onto an empty canvas.
+    pub fn new(x: f64, y: f64) -> Turtle {
+        Turtle::with_canvas(Canvas::new(0, 0), x, y)
+    }
+
+    /// Creates a new `Turtle` at the given position, drawing onto an existing canvas.
+    pub fn with_canvas(canvas: Canvas, x: f64, y: f64) -> Turtle {
+        Turtle {
+            canvas: canvas,
+            state: State { x: x, y: y, heading: 0.0, pen: true },
+            stack: vec![],
+        }
+    }
+
+    /// Returns the current position of the turtle.
+    pub fn position(&self) -> (f64, f64) {
+        (self.state.x, self.state.y)
+    }
+
+    /// Returns the current heading of the turtle in degrees.
+    pub fn heading(&self) -> f64 {
+        self.state.heading
+    }
+
+    /// Turns the turtle to face the given heading in degrees.
+    pub fn set_heading(&mut self, heading: f64) {
+        self.state.heading = heading;
+    }
+
+    /// Detects whether the turtle's pen is down, i.e. whether it draws as it moves.
+    pub fn is_down(&self) -> bool {
+        self.state.pen
+    }
+
+    /// Lifts the pen so that the turtle stops drawing as it moves.
+    pub fn up(&mut self) {
+        self.state.pen = false;
+    }
+
+    /// Puts the pen down so that the turtle draws as it moves.
+    pub fn down(&mut self) {
+        self.state.pen = true;
+    }
+
+    /// Moves the turtle `step` pixels along its heading.
+    pub fn forward(&mut self, step: f64) {
+        let angle = self.state.heading.to_radians();
+        let x = self.state.x + angle.cos() * step;
+        let y = self.state.y + angle.sin() * step;
+        self.move_to(x, y);
+    }
+
+    /// Moves the turtle `step` pixels backwards, without changing its heading.
+    pub fn back(&mut self, step: f64) {
+        self.forward(-step);
+    }
+
+    /// Turns the turtle `angle` degrees clockwise.
+    pub fn right(&mut self, angle: f64) {
+        self.state.heading += angle;
+    }
+
+    /// Turns the turtle `angle` degrees anticlockwise.
+    pub fn left(&mut self, angle: f64) {
+        self.state.heading -= angle;
+    }
+
+    /// Moves the turtle straight to the given position without changing its heading, drawing a
+    /// line if the pen is down.
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        if self.state.pen {
+            self.canvas.line_f64(self.state.x, self.state.y, x, y);
+        }
+        self.state.x = x;
+        self.state.y = y;
+    }
+
+    /// Saves the turtle's position, heading and pen state so that they can be restored later with
+    /// `pop`.
+    pub fn push(&mut self) {
+        self.stack.push(self.state.clone());
+    }
+
+    /// Restores the most recently saved state without drawing anything. Does nothing if there is
+    /// no saved state.
+    pub fn pop(&mut self) {
+        match self.stack.pop() {
+            Some(state) => self.state = state,
+            None => {},
+        }
+    }
+}
+
+impl Render for Turtle {
+    fn rows(&self) -> Vec<String> {
+        self.canvas.rows()
+    }
+
+    fn cells(&self) -> Vec<Vec<String>> {
+        self.canvas.cells()
+    }
+
+    fn render_to<W: Writer>(&self, out: &mut W) -> IoResult<()> {
+        self.canvas.render_to(out)
+    }
+}